@@ -0,0 +1,470 @@
+use crate::detect::prescan_charset;
+use crate::error::Error;
+use crate::limit::{LimitCheck, LimitPolicy, OutputLimit};
+use encoding_rs::Encoding;
+
+/// The amount of leading bytes the charset prescan looks at by default.
+const DEFAULT_PRESCAN_WINDOW: usize = 1024;
+
+/// The input chunk decoded between output limit checks.
+const LIMIT_CHUNK_LEN: usize = 8192;
+
+/// A reusable decoding configuration.
+///
+/// The encoding is resolved in order from the declared charset, the byte order mark,
+/// the locale hint and finally the content itself.
+///
+/// ```rust
+/// use auto_encoder::AutoEncoder;
+///
+/// let encoder = AutoEncoder::builder().locale("ja-jp").tld("jp").build();
+/// assert_eq!(encoder.decode(b"\x82\xA0").unwrap(), "\u{3042}");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoEncoder {
+    pub(crate) prescan_window: usize,
+    fallback: Option<&'static Encoding>,
+    tld: Option<Box<[u8]>>,
+    locale: Option<&'static Encoding>,
+    trust_declared_charset: bool,
+    pub(crate) strict: bool,
+    pub(crate) output_limit: Option<OutputLimit>,
+    pub(crate) limit_policy: LimitPolicy,
+    #[cfg(feature = "normalization")]
+    normalization: Option<crate::normalize::Normalization>,
+    pub(crate) rewrite_declared_charset: bool,
+}
+
+impl Default for AutoEncoder {
+    fn default() -> Self {
+        AutoEncoderBuilder::new().build()
+    }
+}
+
+impl AutoEncoder {
+    /// Start building a configuration from the defaults.
+    pub fn builder() -> AutoEncoderBuilder {
+        AutoEncoderBuilder::new()
+    }
+
+    /// Resolve the encoding of the content.
+    pub fn detect(&self, html: &[u8]) -> Result<&'static Encoding, Error> {
+        if let Some(encoding) = self.detect_declared(html) {
+            return encoding;
+        }
+
+        let (encoding, confident) = crate::guess_encoding(html, self.tld.as_deref());
+        match self.fallback {
+            Some(fallback) if !confident => Ok(fallback),
+            _ => Ok(encoding),
+        }
+    }
+
+    /// Resolve the encoding from the declared charset, byte order mark or locale,
+    /// `None` when it takes the whole content to detect.
+    pub(crate) fn detect_declared(&self, html: &[u8]) -> Option<Result<&'static Encoding, Error>> {
+        if self.trust_declared_charset {
+            let window = &html[..html.len().min(self.prescan_window)];
+            if let Some(label) = prescan_charset(window) {
+                return Some(match (Encoding::for_label(label.as_bytes()), self.fallback) {
+                    (Some(encoding), _) => Ok(encoding),
+                    (None, Some(fallback)) => Ok(fallback),
+                    (None, None) => Err(Error::UnknownLabel(label)),
+                });
+            }
+        }
+
+        if let Some((encoding, _)) = Encoding::for_bom(html) {
+            return Some(Ok(encoding));
+        }
+
+        self.locale.map(Ok)
+    }
+
+    /// Get the content with proper encoding.
+    pub fn decode(&self, html: &[u8]) -> Result<String, Error> {
+        let mut output = String::new();
+        self.decode_into(html, &mut output)?;
+        Ok(output)
+    }
+
+    /// Append the content with proper encoding to `output`. Nothing is appended on error.
+    pub fn decode_into(&self, html: &[u8], output: &mut String) -> Result<(), Error> {
+        if html.is_empty() {
+            return Ok(());
+        }
+        let encoding = self.detect(html)?;
+        self.decode_with_encoding_into(html, encoding, output)
+    }
+
+    /// Get the content with proper encoding along with a [`DecodeReport`] of how it was decoded.
+    pub fn decode_with_report(&self, html: &[u8]) -> Result<(String, DecodeReport), Error> {
+        let mut output = String::new();
+        let report = self.decode_with_report_into(html, &mut output)?;
+        Ok((output, report))
+    }
+
+    /// Append the content with proper encoding to `output`, reporting how it was decoded. Nothing is appended on error.
+    pub fn decode_with_report_into(
+        &self,
+        html: &[u8],
+        output: &mut String,
+    ) -> Result<DecodeReport, Error> {
+        let encoding = if html.is_empty() {
+            encoding_rs::UTF_8
+        } else {
+            self.detect(html)?
+        };
+        let truncated = self.decode_reporting(html, encoding, output)?;
+        Ok(DecodeReport {
+            encoding,
+            truncated,
+        })
+    }
+
+    /// Get the content with proper encoding and an [`OffsetMap`] sampled roughly every `interval` characters.
+    /// Malformed input is replaced and the output limit does not apply.
+    pub fn decode_with_offsets(
+        &self,
+        html: &[u8],
+        interval: usize,
+    ) -> Result<(String, crate::OffsetMap), Error> {
+        let encoding = if html.is_empty() {
+            encoding_rs::UTF_8
+        } else {
+            self.detect(html)?
+        };
+        Ok(crate::decode_with_offsets(html, encoding, interval))
+    }
+
+    /// Get the content with the encoding of the label like SHIFT_JIS, skipping detection.
+    pub fn decode_with_label(&self, html: &[u8], label: &str) -> Result<String, Error> {
+        let mut output = String::new();
+        self.decode_with_label_into(html, label, &mut output)?;
+        Ok(output)
+    }
+
+    /// Append the content with the encoding of the label like SHIFT_JIS to `output`. Nothing is appended on error.
+    pub fn decode_with_label_into(
+        &self,
+        html: &[u8],
+        label: &str,
+        output: &mut String,
+    ) -> Result<(), Error> {
+        let encoding = Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| Error::UnknownLabel(label.to_string()))?;
+        self.decode_with_encoding_into(html, encoding, output)
+    }
+
+    /// Append the content with the encoding to `output`. Nothing is appended on error.
+    pub fn decode_with_encoding_into(
+        &self,
+        html: &[u8],
+        encoding: &'static Encoding,
+        output: &mut String,
+    ) -> Result<(), Error> {
+        self.decode_reporting(html, encoding, output).map(|_| ())
+    }
+
+    /// Decode and post-process the output, returning whether it was truncated at the output limit.
+    fn decode_reporting(
+        &self,
+        html: &[u8],
+        encoding: &'static Encoding,
+        output: &mut String,
+    ) -> Result<bool, Error> {
+        let start = output.len();
+        let truncated = match self.decode_checked(html, encoding, start, output) {
+            Ok(truncated) => truncated,
+            Err(error) => {
+                output.truncate(start);
+                return Err(error);
+            }
+        };
+        // the rewrite looks at the output of the prescanned input, so it runs before normalization
+        if self.rewrite_declared_charset {
+            let window = crate::rewrite::decoded_window_len(html, encoding, self.prescan_window);
+            crate::rewrite::rewrite_tail(output, start, window);
+        }
+        #[cfg(feature = "normalization")]
+        if let Some(form) = self.normalization {
+            crate::normalize::normalize_tail(output, start, form);
+        }
+        // post-processing can grow the output, so the limit is enforced again
+        match self.limit_exceeded(start, output) {
+            Ok(cut) => Ok(truncated || cut),
+            Err(error) => {
+                output.truncate(start);
+                Err(error)
+            }
+        }
+    }
+
+    /// Get the content with proper encoding as UTF-8 [`bytes::Bytes`].
+    /// Content that already decodes to itself is sliced out of `html` without copying.
+    #[cfg(feature = "bytes")]
+    pub fn decode_bytes(&self, html: impl Into<bytes::Bytes>) -> Result<bytes::Bytes, Error> {
+        let html = html.into();
+        if html.is_empty() {
+            return Ok(html);
+        }
+        let encoding = self.detect(&html)?;
+        self.decode_bytes_with_encoding(html, encoding)
+    }
+
+    /// Get the content with the encoding of the label like SHIFT_JIS as UTF-8 [`bytes::Bytes`], skipping detection.
+    #[cfg(feature = "bytes")]
+    pub fn decode_bytes_with_label(
+        &self,
+        html: impl Into<bytes::Bytes>,
+        label: &str,
+    ) -> Result<bytes::Bytes, Error> {
+        let encoding = Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| Error::UnknownLabel(label.to_string()))?;
+        self.decode_bytes_with_encoding(html.into(), encoding)
+    }
+
+    /// Decode into [`bytes::Bytes`], reusing `html` when decoding would not change it.
+    #[cfg(feature = "bytes")]
+    fn decode_bytes_with_encoding(
+        &self,
+        html: bytes::Bytes,
+        encoding: &'static Encoding,
+    ) -> Result<bytes::Bytes, Error> {
+        if let Some(range) = self.unchanged_range(&html, encoding) {
+            return Ok(html.slice(range));
+        }
+        let mut output = String::new();
+        self.decode_with_encoding_into(&html, encoding, &mut output)?;
+        Ok(bytes::Bytes::from(output))
+    }
+
+    /// The range of `html` that decoding with the encoding would output as is, if any.
+    #[cfg(feature = "bytes")]
+    fn unchanged_range(
+        &self,
+        html: &[u8],
+        encoding: &'static Encoding,
+    ) -> Option<std::ops::Range<usize>> {
+        #[cfg(feature = "normalization")]
+        if self.normalization.is_some() {
+            return None;
+        }
+        if self.rewrite_declared_charset {
+            return None;
+        }
+        // the decoder switches to UTF-8 on a UTF-8 byte order mark whatever the encoding
+        let start = if html.starts_with(b"\xEF\xBB\xBF") {
+            3
+        } else {
+            0
+        };
+        let body = &html[start..];
+        if start == 0
+            && encoding != encoding_rs::UTF_8
+            && !(encoding.is_ascii_compatible() && Encoding::ascii_valid_up_to(body) == body.len())
+        {
+            return None;
+        }
+        let text = std::str::from_utf8(body).ok()?;
+        if let Some(limit) = self.output_limit {
+            if LimitCheck::new(limit, 0).exceeded(text).is_some() {
+                return None;
+            }
+        }
+        Some(start..html.len())
+    }
+
+    /// Decode applying the strictness and output limit, `start` being the length of `output` before decoding.
+    /// Returns whether the output was truncated at the limit.
+    fn decode_checked(
+        &self,
+        html: &[u8],
+        encoding: &'static Encoding,
+        start: usize,
+        output: &mut String,
+    ) -> Result<bool, Error> {
+        if !self.strict && self.output_limit.is_none() {
+            crate::decode_into(html, encoding, output);
+            return Ok(false);
+        }
+
+        let mut decoder = encoding.new_decoder();
+        let mut check = self.output_limit.map(|limit| LimitCheck::new(limit, start));
+        let chunk_len = match check {
+            Some(_) => LIMIT_CHUNK_LEN,
+            None => html.len(),
+        };
+        let mut total_read = 0usize;
+
+        loop {
+            let end = html.len().min(total_read + chunk_len);
+            let last = end == html.len();
+            let chunk = &html[total_read..end];
+            if self.strict {
+                crate::decode_strict_chunk(&mut decoder, chunk, total_read, last, output)?;
+            } else {
+                crate::decode_chunk(&mut decoder, chunk, last, output);
+            }
+            total_read = end;
+
+            if let Some(cut) = check.as_mut().and_then(|check| check.exceeded(output)) {
+                self.cut_at_limit(start, cut, output)?;
+                return Ok(true);
+            }
+            if last {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Cut the output after `start` down to the limit, returning whether it was over the limit.
+    fn limit_exceeded(&self, start: usize, output: &mut String) -> Result<bool, Error> {
+        let Some(limit) = self.output_limit else {
+            return Ok(false);
+        };
+        match LimitCheck::new(limit, start).exceeded(output) {
+            Some(cut) => self.cut_at_limit(start, cut, output).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Apply the limit policy to output that went past the limit at the character boundary `cut`.
+    #[cfg_attr(not(feature = "graphemes"), allow(unused_variables))]
+    fn cut_at_limit(&self, start: usize, cut: usize, output: &mut String) -> Result<(), Error> {
+        let cut = match self.limit_policy {
+            LimitPolicy::Error => {
+                return Err(Error::OutputLimit {
+                    limit: self.output_limit.map_or(0, OutputLimit::value),
+                })
+            }
+            LimitPolicy::Truncate => cut,
+            #[cfg(feature = "graphemes")]
+            LimitPolicy::TruncateGraphemes => crate::limit::grapheme_floor(output, start, cut),
+        };
+        output.truncate(cut);
+        Ok(())
+    }
+}
+
+/// How [`AutoEncoder::decode_with_report`] decoded the content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeReport {
+    /// The encoding the content was decoded with.
+    pub encoding: &'static Encoding,
+    /// Whether the output was cut short at the output limit.
+    pub truncated: bool,
+}
+
+/// Builder for [`AutoEncoder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoEncoderBuilder {
+    config: AutoEncoder,
+}
+
+impl Default for AutoEncoderBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AutoEncoderBuilder {
+    /// Start from the defaults used by [`crate::auto_encode_bytes`].
+    pub fn new() -> Self {
+        Self {
+            config: AutoEncoder {
+                prescan_window: DEFAULT_PRESCAN_WINDOW,
+                fallback: None,
+                tld: None,
+                locale: None,
+                trust_declared_charset: true,
+                strict: false,
+                output_limit: None,
+                limit_policy: LimitPolicy::Error,
+                #[cfg(feature = "normalization")]
+                normalization: None,
+                rewrite_declared_charset: false,
+            },
+        }
+    }
+
+    /// The amount of leading bytes searched for a meta charset. Defaults to 1024.
+    pub fn prescan_window(mut self, prescan_window: usize) -> Self {
+        self.config.prescan_window = prescan_window;
+        self
+    }
+
+    /// The encoding used when the declared charset label is unknown or content detection is not confident.
+    /// Without a fallback an unknown label is an error.
+    pub fn fallback(mut self, fallback: &'static Encoding) -> Self {
+        self.config.fallback = Some(fallback);
+        self
+    }
+
+    /// The top-level domain the content came from like "jp" or "example.co.jp", used by content detection.
+    /// Domains with non-ASCII characters are ignored, pass them in Punycode.
+    pub fn tld(mut self, tld: &str) -> Self {
+        let label = tld.rsplit('.').next().unwrap_or_default();
+        self.config.tld = if label.is_empty() || !label.is_ascii() {
+            None
+        } else {
+            Some(label.to_ascii_lowercase().into_bytes().into_boxed_slice())
+        };
+        self
+    }
+
+    /// The locale or language like "ja-jp" used before content detection. Unknown locales are ignored.
+    pub fn locale(mut self, locale: &str) -> Self {
+        self.config.locale = crate::encoding_for_locale(locale);
+        self
+    }
+
+    /// Whether a charset declared in a meta element is used. Defaults to true.
+    pub fn trust_declared_charset(mut self, trust_declared_charset: bool) -> Self {
+        self.config.trust_declared_charset = trust_declared_charset;
+        self
+    }
+
+    /// Fail on malformed input instead of replacing it with U+FFFD. Defaults to false.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.config.strict = strict;
+        self
+    }
+
+    /// The maximum length of the decoded output in bytes. Decoding stops once exceeded, see [`Self::limit_policy`].
+    pub fn max_output_len(mut self, max_output_len: usize) -> Self {
+        self.config.output_limit = Some(OutputLimit::Bytes(max_output_len));
+        self
+    }
+
+    /// The maximum number of characters in the decoded output. Decoding stops once exceeded, see [`Self::limit_policy`].
+    pub fn max_output_chars(mut self, max_output_chars: usize) -> Self {
+        self.config.output_limit = Some(OutputLimit::Chars(max_output_chars));
+        self
+    }
+
+    /// What happens once the decoded output exceeds the limit. Defaults to [`LimitPolicy::Error`].
+    pub fn limit_policy(mut self, limit_policy: LimitPolicy) -> Self {
+        self.config.limit_policy = limit_policy;
+        self
+    }
+
+    /// Normalize the decoded text to the Unicode form.
+    #[cfg(feature = "normalization")]
+    pub fn normalization(mut self, form: crate::normalize::Normalization) -> Self {
+        self.config.normalization = Some(form);
+        self
+    }
+
+    /// Rewrite the charset declared by the decoded HTML to UTF-8 and strip a leading byte order mark.
+    /// Defaults to false.
+    pub fn rewrite_declared_charset(mut self, rewrite_declared_charset: bool) -> Self {
+        self.config.rewrite_declared_charset = rewrite_declared_charset;
+        self
+    }
+
+    /// Finish the configuration.
+    pub fn build(self) -> AutoEncoder {
+        self.config
+    }
+}
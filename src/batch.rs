@@ -0,0 +1,47 @@
+use crate::decoder::{DecodeHint, Decoder};
+
+/// Get the content with proper encoding for many documents.
+///
+/// Every document is decoded into one reused [`Decoder`] buffer and copied out at its
+/// exact length, so the worst case output reservation is made once per batch instead
+/// of once per document as when calling [`crate::auto_encode_bytes`] in a loop.
+pub fn decode_batch(documents: &[&[u8]]) -> Vec<String> {
+    let mut decoder = Decoder::new();
+    documents
+        .iter()
+        .map(|html| decoder.decode(html).to_owned())
+        .collect()
+}
+
+/// Get the content with proper encoding for many documents with a hint per document.
+pub fn decode_batch_with_hints(documents: &[(&[u8], DecodeHint<'_>)]) -> Vec<String> {
+    let mut decoder = Decoder::new();
+    documents
+        .iter()
+        .map(|&(html, hint)| decoder.decode_with_hint(html, hint).to_owned())
+        .collect()
+}
+
+/// Get the content with proper encoding for many documents in parallel, with one [`Decoder`] per rayon worker.
+#[cfg(feature = "rayon")]
+pub fn par_decode_batch(documents: &[&[u8]]) -> Vec<String> {
+    use rayon::prelude::*;
+
+    documents
+        .par_iter()
+        .map_init(Decoder::new, |decoder, html| decoder.decode(html).to_owned())
+        .collect()
+}
+
+/// Get the content with proper encoding for many documents in parallel with a hint per document.
+#[cfg(feature = "rayon")]
+pub fn par_decode_batch_with_hints(documents: &[(&[u8], DecodeHint<'_>)]) -> Vec<String> {
+    use rayon::prelude::*;
+
+    documents
+        .par_iter()
+        .map_init(Decoder::new, |decoder, &(html, hint)| {
+            decoder.decode_with_hint(html, hint).to_owned()
+        })
+        .collect()
+}
@@ -0,0 +1,23 @@
+use bytes::Bytes;
+
+/// Get the content with proper encoding as UTF-8 [`Bytes`].
+/// Content that is already UTF-8 is returned without copying, minus any byte order mark.
+///
+/// ```rust
+/// let html = bytes::Bytes::from_static(b"<p>hello</p>");
+/// let text = auto_encoder::auto_encode_to_bytes(html.clone());
+/// assert_eq!(text.as_ptr(), html.as_ptr());
+/// ```
+pub fn auto_encode_to_bytes(html: impl Into<Bytes>) -> Bytes {
+    crate::AutoEncoder::default()
+        .decode_bytes(html)
+        .unwrap_or_default()
+}
+
+/// Get the content with proper encoding as UTF-8 [`Bytes`]. Pass in a proper encoding label like SHIFT_JIS.
+/// Content that decodes to itself is returned without copying.
+pub fn encode_to_bytes(html: impl Into<Bytes>, label: &str) -> Bytes {
+    crate::AutoEncoder::default()
+        .decode_bytes_with_label(html, label)
+        .unwrap_or_default()
+}
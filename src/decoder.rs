@@ -0,0 +1,84 @@
+/// A hint for resolving the encoding of a document.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeHint<'a> {
+    /// Detect the encoding like [`crate::auto_encode_bytes`].
+    #[default]
+    Auto,
+    /// Use the encoding label like SHIFT_JIS like [`crate::encode_bytes`].
+    Label(&'a str),
+    /// Use the encoding for the language like "ja" like [`crate::encode_bytes_from_language`].
+    Language(&'a str),
+}
+
+/// A reusable decoding context that keeps its output buffer between documents.
+///
+/// Create one per worker thread and feed it documents one at a time. Each decode
+/// resets the context, so the returned text is only valid until the next call.
+#[derive(Debug, Default, Clone)]
+pub struct Decoder {
+    output: String,
+}
+
+impl Decoder {
+    /// Create a new decoding context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new decoding context with room for `capacity` bytes of output.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            output: String::with_capacity(capacity),
+        }
+    }
+
+    /// Get the content with proper encoding. Same as [`crate::auto_encode_bytes`].
+    pub fn decode(&mut self, html: &[u8]) -> &str {
+        self.reset();
+        crate::auto_encode_into(html, &mut self.output);
+        &self.output
+    }
+
+    /// Get the content with proper encoding. Same as [`crate::encode_bytes`].
+    pub fn decode_with_label(&mut self, html: &[u8], label: &str) -> &str {
+        self.reset();
+        crate::encode_bytes_into(html, label, &mut self.output);
+        &self.output
+    }
+
+    /// Get the content with proper encoding from a language. Same as [`crate::encode_bytes_from_language`].
+    pub fn decode_with_language(&mut self, html: &[u8], language: &str) -> &str {
+        self.reset();
+        crate::encode_bytes_from_language_into(html, language, &mut self.output);
+        &self.output
+    }
+
+    /// Get the content with proper encoding using the hint.
+    pub fn decode_with_hint(&mut self, html: &[u8], hint: DecodeHint<'_>) -> &str {
+        match hint {
+            DecodeHint::Auto => self.decode(html),
+            DecodeHint::Label(label) => self.decode_with_label(html, label),
+            DecodeHint::Language(language) => self.decode_with_language(html, language),
+        }
+    }
+
+    /// The text of the last decoded document.
+    pub fn as_str(&self) -> &str {
+        &self.output
+    }
+
+    /// Clear the last decoded document while keeping the allocation.
+    pub fn reset(&mut self) {
+        self.output.clear();
+    }
+
+    /// Take the text of the last decoded document, leaving an empty buffer behind.
+    pub fn take(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+
+    /// The capacity of the output buffer in bytes.
+    pub fn capacity(&self) -> usize {
+        self.output.capacity()
+    }
+}
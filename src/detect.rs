@@ -1,64 +1,411 @@
-use crate::meta::{HtmlMetadata, ASSET_NUMBERS, FIRST_BYTE_MAP};
-
-/// Magic byte signatures grouped by first byte for single-pass matching.
-/// Sorted longest-first within each group so longer signatures match before shorter prefixes.
-static MAGIC_TABLE: &[(u8, &[&[u8]])] = &[
-    (0x00, &[&[0x00, 0x00, 0x01, 0xBA], &[0x00, 0x00, 0x01, 0xB3], &[0x00, 0x00, 0x00, 0x18]]),
-    (0x1A, &[&[0x1A, 0x45, 0xDF, 0xA3]]),
-    (0x1F, &[&[0x1F, 0x8B]]),
-    (0x25, &[b"%PDF"]),
-    (0x42, &[&[0x42, 0x5A, 0x68], &[0x42, 0x4D]]),
-    (0x46, &[&[0x46, 0x4C, 0x56, 0x01]]),
-    (0x47, &[&[0x47, 0x49, 0x46, 0x38]]),
-    (0x49, &[&[0x49, 0x49, 0x2A, 0x00], &[0x49, 0x49, 0x2B, 0x00], &[0x49, 0x44, 0x33]]),
-    (0x4C, &[&[0x4C]]),
-    (0x4D, &[&[0x4D, 0x4D, 0x00, 0x2A], &[0x4D, 0x4D, 0x00, 0x2B]]),
-    (0x4F, &[&[0x4F, 0x67, 0x67, 0x53]]),
-    (0x50, &[&[0x50, 0x4B, 0x03, 0x04]]),
-    (0x52, &[&[0x52, 0x49, 0x46, 0x46]]),
-    (0x66, &[&[0x66, 0x4C, 0x61, 0x43]]),
-    (0x7F, &[&[0x7F, 0x45, 0x4C, 0x46]]),
-    (0x89, &[&[0x89, 0x50, 0x4E, 0x47]]),
-    (0xCA, &[&[0xCA, 0xFE, 0xBA, 0xBE]]),
-    (0xFF, &[&[0xFF, 0xD8, 0xFF], &[0xFF, 0xFB]]),
+use crate::file_type::FileType;
+use crate::meta::HtmlMetadata;
+use std::ops::Range;
+
+/// Bytes expected at an offset of the content, compared under a mask when one is set.
+struct Fragment {
+    offset: usize,
+    bytes: &'static [u8],
+    mask: &'static [u8],
+}
+
+impl Fragment {
+    const fn at(offset: usize, bytes: &'static [u8]) -> Self {
+        Self::masked(offset, bytes, &[])
+    }
+
+    /// Compare only the bits set in the mask, so a 0x00 mask byte matches any byte.
+    const fn masked(offset: usize, bytes: &'static [u8], mask: &'static [u8]) -> Self {
+        Self {
+            offset,
+            bytes,
+            mask,
+        }
+    }
+
+    #[inline]
+    fn matches(&self, content: &[u8]) -> bool {
+        match content.get(self.offset..self.offset + self.bytes.len()) {
+            Some(window) if self.mask.is_empty() => window == self.bytes,
+            Some(window) => window
+                .iter()
+                .zip(self.bytes)
+                .zip(self.mask)
+                .all(|((&byte, &expected), &mask)| byte & mask == expected & mask),
+            None => false,
+        }
+    }
+}
+
+/// The prefix and fragments that all have to be present to identify a file format,
+/// along with a check of the header for formats that need more than fixed bytes.
+pub(crate) struct Signature {
+    file_type: FileType,
+    prefix: &'static [u8],
+    fragments: &'static [Fragment],
+    check: Option<fn(&[u8]) -> bool>,
+}
+
+impl Signature {
+    const fn new(
+        file_type: FileType,
+        prefix: &'static [u8],
+        fragments: &'static [Fragment],
+    ) -> Self {
+        Self {
+            file_type,
+            prefix,
+            fragments,
+            check: None,
+        }
+    }
+
+    /// Also require the check to pass on the content.
+    const fn checked(mut self, check: fn(&[u8]) -> bool) -> Self {
+        self.check = Some(check);
+        self
+    }
+
+    /// A signature made of the prefix only.
+    const fn prefix(file_type: FileType, prefix: &'static [u8]) -> Self {
+        Self::new(file_type, prefix, &[])
+    }
+
+    #[inline]
+    fn matches(&self, content: &[u8]) -> bool {
+        content.starts_with(self.prefix)
+            && self.fragments.iter().all(|fragment| fragment.matches(content))
+            && self.check.is_none_or(|check| check(content))
+    }
+}
+
+/// Magic byte signatures with a prefix, grouped by first byte for single-pass matching.
+/// Sorted most specific first within each group so longer signatures match before shorter prefixes.
+pub(crate) static MAGIC_TABLE: &[(u8, &[Signature])] = &[
+    (0x00, &[
+        Signature::prefix(FileType::Wasm, b"\x00asm\x01\x00\x00\x00"),
+        Signature::prefix(FileType::JpegXl, &[0x00, 0x00, 0x00, 0x0C, 0x4A, 0x58, 0x4C, 0x20, 0x0D, 0x0A, 0x87, 0x0A]),
+        Signature::new(FileType::Mpeg, &[0x00, 0x00, 0x01, 0xBA], &[Fragment::masked(4, &[0x44], &[0xC4])]),
+        Signature::new(FileType::Mpeg, &[0x00, 0x00, 0x01, 0xBA], &[Fragment::masked(4, &[0x21], &[0xF1])]),
+        Signature::prefix(FileType::Mpeg, &[0x00, 0x00, 0x01, 0xB3]),
+        Signature::prefix(FileType::Ico, &[0x00, 0x00, 0x01, 0x00]).checked(is_icon_directory),
+        Signature::prefix(FileType::Cur, &[0x00, 0x00, 0x02, 0x00]).checked(is_icon_directory),
+        Signature::prefix(FileType::Ttf, &[0x00, 0x01, 0x00, 0x00]).checked(is_sfnt_header),
+        Signature::new(FileType::Mov, &[0x00], &[Fragment::at(4, b"moov")]).checked(is_box_size),
+        Signature::new(FileType::Mov, &[0x00, 0x00, 0x00, 0x08], &[Fragment::at(4, b"wide")]),
+    ]),
+    (0x02, &[Signature::prefix(FileType::Lz4, &[0x02, 0x21, 0x4C, 0x18])]),
+    (0x04, &[Signature::prefix(FileType::Lz4, &[0x04, 0x22, 0x4D, 0x18])]),
+    (0x1A, &[
+        Signature::prefix(FileType::WebM, &[0x1A, 0x45, 0xDF, 0xA3]).checked(is_webm),
+        Signature::prefix(FileType::Mkv, &[0x1A, 0x45, 0xDF, 0xA3]),
+    ]),
+    (0x1F, &[Signature::prefix(FileType::Gzip, &[0x1F, 0x8B]), Signature::prefix(FileType::UnixCompress, &[0x1F, 0x9D])]),
+    (0x21, &[Signature::prefix(FileType::Deb, b"!<arch>\ndebian-binary"), Signature::prefix(FileType::Ar, b"!<arch>\n")]),
+    (0x23, &[Signature::prefix(FileType::Script, b"#!/"), Signature::prefix(FileType::Script, b"#! /")]),
+    (0x25, &[
+        Signature::prefix(FileType::Pdf, b"%PDF"),
+        Signature::prefix(FileType::Eps, b"%!PS-Adobe-").checked(is_eps_header),
+        Signature::prefix(FileType::PostScript, b"%!PS"),
+    ]),
+    (0x28, &[Signature::prefix(FileType::Zstd, &[0x28, 0xB5, 0x2F, 0xFD])]),
+    (0x30, &[
+        Signature::prefix(FileType::Cpio, b"070701").checked(is_cpio_newc_header),
+        Signature::prefix(FileType::Cpio, b"070702").checked(is_cpio_newc_header),
+        Signature::prefix(FileType::Cpio, b"070707").checked(is_cpio_odc_header),
+    ]),
+    (0x37, &[Signature::prefix(FileType::SevenZip, &[0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C])]),
+    (0x38, &[Signature::prefix(FileType::Psd, b"8BPS")]),
+    (0x41, &[
+        Signature::prefix(FileType::Arrow, b"ARROW1\x00\x00"),
+        Signature::new(FileType::DjVu, b"AT&TFORM", &[Fragment::at(12, b"DJV")]),
+    ]),
+    (0x42, &[Signature::prefix(FileType::Bzip2, &[0x42, 0x5A, 0x68]), Signature::prefix(FileType::Bmp, &[0x42, 0x4D])]),
+    (0x46, &[Signature::prefix(FileType::Flv, &[0x46, 0x4C, 0x56, 0x01])]),
+    (0x47, &[
+        Signature::prefix(FileType::Gif, &[0x47, 0x49, 0x46, 0x38]),
+        Signature::new(FileType::MpegTs, &[0x47], &[Fragment::masked(1, &[0x00], &[0x80]), Fragment::at(188, &[0x47]), Fragment::at(376, &[0x47]), Fragment::at(564, &[0x47])]),
+    ]),
+    (0x49, &[Signature::prefix(FileType::Tiff, &[0x49, 0x49, 0x2A, 0x00]), Signature::prefix(FileType::BigTiff, &[0x49, 0x49, 0x2B, 0x00]), Signature::prefix(FileType::Mp3, &[0x49, 0x44, 0x33])]),
+    (0x4D, &[
+        Signature::prefix(FileType::Tiff, &[0x4D, 0x4D, 0x00, 0x2A]),
+        Signature::prefix(FileType::BigTiff, &[0x4D, 0x4D, 0x00, 0x2B]),
+        Signature::prefix(FileType::Midi, b"MThd\x00\x00\x00\x06"),
+        Signature::prefix(FileType::Cab, b"MSCF\x00\x00\x00\x00"),
+        Signature::prefix(FileType::Pe, b"MZ").checked(is_pe_header),
+    ]),
+    (0x4F, &[
+        Signature::new(FileType::Opus, b"OggS", &[Fragment::at(28, b"OpusHead")]),
+        Signature::prefix(FileType::Ogg, &[0x4F, 0x67, 0x67, 0x53]),
+        Signature::prefix(FileType::Otf, b"OTTO").checked(is_sfnt_header),
+    ]),
+    (0x50, &[
+        Signature::prefix(FileType::Zip, &[0x50, 0x4B, 0x03, 0x04]),
+        Signature::prefix(FileType::Zip, &[0x50, 0x4B, 0x05, 0x06]),
+        Signature::prefix(FileType::Zip, &[0x50, 0x4B, 0x07, 0x08]),
+        Signature::prefix(FileType::Parquet, b"PAR1"),
+    ]),
+    (0x52, &[
+        Signature::prefix(FileType::Rar, b"Rar!\x1A\x07\x00"),
+        Signature::prefix(FileType::Rar, b"Rar!\x1A\x07\x01\x00"),
+        Signature::new(FileType::WebP, b"RIFF", &[Fragment::at(8, b"WEBP")]),
+        Signature::new(FileType::Wav, b"RIFF", &[Fragment::at(8, b"WAVE")]),
+        Signature::new(FileType::Avi, b"RIFF", &[Fragment::at(8, b"AVI ")]),
+        Signature::prefix(FileType::Riff, &[0x52, 0x49, 0x46, 0x46]),
+    ]),
+    (0x53, &[
+        Signature::prefix(FileType::Sqlite, b"SQLite format 3\x00"),
+        Signature::new(FileType::Fits, b"SIMPLE  =", &[Fragment::at(29, b"T")]),
+    ]),
+    (0x5D, &[Signature::prefix(FileType::Lzma, &[0x5D, 0x00, 0x00]).checked(is_lzma_header)]),
+    (0x64, &[Signature::new(FileType::Dex, b"dex\n0", &[Fragment::at(7, b"\x00")])]),
+    (0x66, &[Signature::prefix(FileType::Flac, &[0x66, 0x4C, 0x61, 0x43])]),
+    (0x71, &[Signature::prefix(FileType::Cpio, &[0x71, 0xC7])]),
+    (0x74, &[
+        Signature::new(FileType::Ttc, b"ttcf", &[Fragment::at(4, b"\x00"), Fragment::masked(5, &[0x00], &[0xFC]), Fragment::at(6, b"\x00\x00")]),
+        Signature::prefix(FileType::Ttf, b"true").checked(is_sfnt_header),
+    ]),
+    (0x77, &[Signature::prefix(FileType::Woff, b"wOFF"), Signature::prefix(FileType::Woff2, b"wOF2")]),
+    (0x7B, &[Signature::prefix(FileType::Rtf, b"{\\rtf")]),
+    (0x7F, &[Signature::prefix(FileType::Elf, &[0x7F, 0x45, 0x4C, 0x46])]),
+    (0x89, &[Signature::prefix(FileType::Png, &[0x89, 0x50, 0x4E, 0x47])]),
+    (0xC5, &[Signature::prefix(FileType::Eps, &[0xC5, 0xD0, 0xD3, 0xC6])]),
+    (0xC7, &[Signature::prefix(FileType::Cpio, &[0xC7, 0x71])]),
+    (0xCA, &[
+        Signature::prefix(FileType::MachO, &[0xCA, 0xFE, 0xBA, 0xBE]).checked(is_fat_mach_o),
+        Signature::prefix(FileType::MachO, &[0xCA, 0xFE, 0xBA, 0xBF]).checked(is_fat_mach_o),
+        Signature::prefix(FileType::JavaClass, &[0xCA, 0xFE, 0xBA, 0xBE]),
+    ]),
+    (0xCE, &[Signature::prefix(FileType::MachO, &[0xCE, 0xFA, 0xED, 0xFE])]),
+    (0xCF, &[Signature::prefix(FileType::MachO, &[0xCF, 0xFA, 0xED, 0xFE])]),
+    (0xD0, &[Signature::prefix(FileType::Msi, &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]).checked(is_msi)]),
+    (0xED, &[Signature::prefix(FileType::Rpm, &[0xED, 0xAB, 0xEE, 0xDB])]),
+    (0xFD, &[Signature::prefix(FileType::Xz, &[0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00])]),
+    (0xFE, &[Signature::prefix(FileType::MachO, &[0xFE, 0xED, 0xFA, 0xCE]), Signature::prefix(FileType::MachO, &[0xFE, 0xED, 0xFA, 0xCF])]),
+    (0xFF, &[
+        Signature::prefix(FileType::Jpeg, &[0xFF, 0xD8, 0xFF]),
+        Signature::prefix(FileType::JpegXl, &[0xFF, 0x0A]),
+        // ADTS has the layer bits cleared, MP3 frames are layer III
+        Signature::new(FileType::Aac, &[0xFF], &[Fragment::masked(1, &[0xF0], &[0xF6])]),
+        Signature::new(FileType::Mp3, &[0xFF], &[Fragment::masked(1, &[0xE2], &[0xE6])]),
+    ]),
 ];
 
-/// Checks if the file is a known binary format using its initial bytes.
-#[inline]
-pub fn is_binary_file(content: &[u8]) -> bool {
-    if content.is_empty() {
+/// Magic byte signatures without a prefix, checked in order after [`MAGIC_TABLE`].
+static OFFSET_SIGNATURES: &[Signature] = &[
+    Signature::new(FileType::Avif, b"", &[Fragment::at(4, b"ftyp")]).checked(is_avif_brand),
+    Signature::new(FileType::Heic, b"", &[Fragment::at(4, b"ftyp")]).checked(is_heif_brand),
+    Signature::new(FileType::Mov, b"", &[Fragment::at(4, b"ftyp"), Fragment::at(8, b"qt  ")]),
+    Signature::new(FileType::Mp4, b"", &[Fragment::at(4, b"ftyp")]),
+    Signature::new(FileType::Pyc, b"", &[Fragment::at(2, b"\r\n")]).checked(is_pyc_magic),
+    Signature::new(FileType::Eot, b"", &[Fragment::at(34, b"LP")]).checked(is_eot_version),
+    Signature::new(FileType::Lha, b"", &[Fragment::at(2, b"-l"), Fragment::at(6, b"-")]).checked(is_lha_header),
+    Signature::new(FileType::Tar, b"", &[Fragment::at(257, b"ustar\x00"), Fragment::at(263, b"00")]),
+    Signature::new(FileType::Tar, b"", &[Fragment::at(257, b"ustar  \x00")]),
+    Signature::new(FileType::Iso9660, b"", &[Fragment::at(32769, b"CD001")]),
+    Signature::new(FileType::Iso9660, b"", &[Fragment::at(34817, b"CD001")]),
+    Signature::new(FileType::Iso9660, b"", &[Fragment::at(36865, b"CD001")]),
+];
+
+/// Check the method and size of an LHA header, the first byte being the header size instead of a magic number.
+fn is_lha_header(content: &[u8]) -> bool {
+    // the fragments already matched "-l" at offset 2 and "-" at offset 6
+    let method = matches!(
+        (content[4], content[5]),
+        (b'h', b'0'..=b'7' | b'd') | (b'z', b's' | b'4' | b'5')
+    );
+    // levels 0 and 1 store the size after the first two bytes, level 2 the total size as two bytes
+    let size = match content.get(20) {
+        Some(0 | 1) => usize::from(content[0]) >= 22,
+        Some(2) => usize::from(u16::from_le_bytes([content[0], content[1]])) >= 26,
+        _ => false,
+    };
+    method && size
+}
+
+/// Check that the big endian size of the leading box covers at least its own header.
+fn is_box_size(content: &[u8]) -> bool {
+    u32::from_be_bytes([content[0], content[1], content[2], content[3]]) >= 8
+}
+
+/// Check that an ICO or CUR header lists at least one image with a cleared reserved byte.
+fn is_icon_directory(content: &[u8]) -> bool {
+    content.len() >= 10 && (content[4] != 0 || content[5] != 0) && content[9] == 0
+}
+
+/// Check for the DocType of a WebM EBML header.
+fn is_webm(content: &[u8]) -> bool {
+    let header = &content[..content.len().min(64)];
+    let Some(start) = find_short(header, &[0x42, 0x82]).map(|id| id + 2) else {
         return false;
-    }
-    let first = content[0];
-    if let Ok(idx) = MAGIC_TABLE.binary_search_by_key(&first, |&(b, _)| b) {
-        let (_, signatures) = MAGIC_TABLE[idx];
-        for sig in signatures.iter() {
-            if content.len() >= sig.len() && &content[..sig.len()] == *sig {
-                return true;
-            }
+    };
+    // a one byte size has the marker bit set
+    match header.get(start) {
+        Some(&size) if size & 0x80 != 0 => {
+            let end = start + 1 + usize::from(size & 0x7F);
+            header.get(start + 1..end) == Some(b"webm")
         }
+        _ => false,
     }
-    false
 }
 
-/// Checks if the file is a known binary format using its initial bytes.
-/// Uses the original PHF map implementation for backwards compatibility.
-#[inline]
-pub fn is_binary_file_phf(content: &[u8]) -> bool {
-    if content.is_empty() {
+/// Check for an AVIF major brand or compatible brand in an ftyp box.
+fn is_avif_brand(content: &[u8]) -> bool {
+    let size = u32::from_be_bytes([content[0], content[1], content[2], content[3]]) as usize;
+    let brands = content.get(16..size.min(content.len())).unwrap_or_default();
+    content
+        .get(8..12)
+        .into_iter()
+        .chain(brands.chunks_exact(4))
+        .any(|brand| brand == b"avif" || brand == b"avis")
+}
+
+/// Check for a HEIF major brand like "heic" or "mif1" in an ftyp box.
+fn is_heif_brand(content: &[u8]) -> bool {
+    matches!(
+        content.get(8..12),
+        Some(b"heic" | b"heix" | b"heim" | b"heis" | b"hevc" | b"hevx" | b"mif1" | b"msf1")
+    )
+}
+
+/// Check that the fields after a newc or crc cpio magic are hexadecimal.
+fn is_cpio_newc_header(content: &[u8]) -> bool {
+    content
+        .get(6..110)
+        .is_some_and(|fields| fields.iter().all(u8::is_ascii_hexdigit))
+}
+
+/// Check that the fields after an odc cpio magic are octal.
+fn is_cpio_odc_header(content: &[u8]) -> bool {
+    content
+        .get(6..76)
+        .is_some_and(|fields| fields.iter().all(|byte| matches!(byte, b'0'..=b'7')))
+}
+
+/// Check the dictionary and uncompressed sizes of an LZMA alone header with the default properties.
+fn is_lzma_header(content: &[u8]) -> bool {
+    let Some(header) = content.get(..13) else {
+        return false;
+    };
+    // the dictionary size is 2^n or 2^n + 2^(n-1), the size is unknown or below 2^48
+    let dictionary = u32::from_le_bytes([header[1], header[2], header[3], header[4]]);
+    let reduced = dictionary >> dictionary.trailing_zeros();
+    let size = u64::from_le_bytes([
+        header[5], header[6], header[7], header[8], header[9], header[10], header[11], header[12],
+    ]);
+    dictionary >= 1 << 12 && (reduced == 1 || reduced == 3) && (size == u64::MAX || size < 1 << 48)
+}
+
+/// Check that a DOS header points to a PE header, or looks like a PE stub when the content stops before it.
+fn is_pe_header(content: &[u8]) -> bool {
+    let Some(lfanew) = content.get(0x3C..0x40) else {
         return false;
+    };
+    let lfanew = u32::from_le_bytes([lfanew[0], lfanew[1], lfanew[2], lfanew[3]]) as usize;
+    match content.get(lfanew..lfanew.saturating_add(4)) {
+        Some(signature) => signature == b"PE\x00\x00",
+        // PE stubs keep their relocation table right after the 64 byte DOS header
+        None => content[0x18..0x1A] == [0x40, 0x00],
     }
+}
 
-    if let Some(&keys) = FIRST_BYTE_MAP.get(&content[0]) {
-        for &key in keys {
-            if let Some(&k) = ASSET_NUMBERS.get(key) {
-                if content.len() >= k.len() && &content[..k.len()] == k {
-                    return true;
-                }
-            }
-        }
+/// Check that `CA FE BA BE` is followed by an architecture count, Java class versions being 45 and up.
+fn is_fat_mach_o(content: &[u8]) -> bool {
+    content
+        .get(4..8)
+        .map(|count| u32::from_be_bytes([count[0], count[1], count[2], count[3]]))
+        .is_some_and(|count| (1..20).contains(&count))
+}
+
+/// Check for the magic number of Python 2.7 or 3 bytecode before "\r\n", followed by
+/// the flags of Python 3.7 and later or the timestamp of earlier versions.
+fn is_pyc_magic(content: &[u8]) -> bool {
+    let magic = u16::from_le_bytes([content[0], content[1]]);
+    let Some(word) = content.get(4..8) else {
+        return false;
+    };
+    let flags = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+    match magic {
+        // only the hash based and check source bits are defined
+        3390..=4000 => matches!(flags, 0 | 1 | 3),
+        62211 | 3000..=3389 => true,
+        _ => false,
     }
-    false
+}
+
+/// Check for the MSI class id on the root entry of a compound file.
+fn is_msi(content: &[u8]) -> bool {
+    const MSI_CLSID: [u8; 16] = [
+        0x84, 0x10, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x46,
+    ];
+    let Some(header) = content.get(..0x34) else {
+        return false;
+    };
+    let sector_shift = u16::from_le_bytes([header[0x1E], header[0x1F]]);
+    if sector_shift != 9 && sector_shift != 12 {
+        return false;
+    }
+    let directory = u32::from_le_bytes([header[0x30], header[0x31], header[0x32], header[0x33]]);
+    // the directory sector number is untrusted and can overflow on 32-bit targets
+    let root = (directory as usize)
+        .checked_add(1)
+        .and_then(|sector| sector.checked_mul(1 << sector_shift))
+        .and_then(|root| Some(root..root.checked_add(0x60)?));
+    root.is_some_and(|root| content.get(root.start + 0x50..root.end) == Some(&MSI_CLSID))
+}
+
+/// Check that the table count of a TrueType or OpenType header agrees with its search range.
+fn is_sfnt_header(content: &[u8]) -> bool {
+    let Some(header) = content.get(4..8) else {
+        return false;
+    };
+    let tables = u16::from_be_bytes([header[0], header[1]]);
+    let search_range = u16::from_be_bytes([header[2], header[3]]);
+    // the search range is 16 times the largest power of two not above the table count
+    (1..=256).contains(&tables) && u32::from(search_range) == 16 << tables.ilog2()
+}
+
+/// Check that the first line of a PostScript header declares an EPS version.
+fn is_eps_header(content: &[u8]) -> bool {
+    let header = &content[..content.len().min(64)];
+    let line = header.split(|&b| b == b'\r' || b == b'\n').next().unwrap_or_default();
+    memchr::memmem::find(line, b" EPSF-").is_some()
+}
+
+/// Check for a known EOT version, the header starting with sizes instead of a magic number.
+fn is_eot_version(content: &[u8]) -> bool {
+    let version = u32::from_le_bytes([content[8], content[9], content[10], content[11]]);
+    matches!(version, 0x0001_0000 | 0x0002_0001 | 0x0002_0002)
+}
+
+/// Detect the file format using its initial bytes.
+#[inline]
+pub fn detect_file_type(content: &[u8]) -> Option<FileType> {
+    let &first = content.first()?;
+    let prefixed = match MAGIC_TABLE.binary_search_by_key(&first, |&(b, _)| b) {
+        Ok(idx) => MAGIC_TABLE[idx].1,
+        Err(_) => &[],
+    };
+    prefixed
+        .iter()
+        .chain(OFFSET_SIGNATURES)
+        .find(|signature| signature.matches(content))
+        .map(|signature| signature.file_type)
+}
+
+/// Checks if the file is a known binary format using its initial bytes.
+/// Scripts are detected by [`detect_file_type`] but are text.
+#[inline]
+pub fn is_binary_file(content: &[u8]) -> bool {
+    detect_file_type(content).is_some_and(FileType::is_binary)
+}
+
+/// Checks if the file is a known binary format using its initial bytes.
+/// Kept for backwards compatibility, same as [`is_binary_file`].
+#[inline]
+pub fn is_binary_file_phf(content: &[u8]) -> bool {
+    is_binary_file(content)
 }
 
 /// Find first byte using memchr SIMD.
@@ -114,7 +461,7 @@ fn find_short(haystack: &[u8], needle: &[u8]) -> Option<usize> {
 
 #[inline(always)]
 fn extract_quoted_or_unquoted(after_attr: &[u8]) -> Option<String> {
-    let &quote = after_attr.get(0)?;
+    let &quote = after_attr.first()?;
     if quote == b'"' || quote == b'\'' {
         let quote_close = find_byte(&after_attr[1..], quote)?;
         std::str::from_utf8(&after_attr[1..quote_close + 1])
@@ -158,22 +505,96 @@ pub fn detect_language(html_content: &[u8]) -> Option<String> {
 /// Detect the encoding used in an HTML file.
 #[inline]
 pub fn detect_encoding(html_content: &[u8]) -> Option<String> {
-    let search_area = &html_content[..html_content.len().min(1024)];
+    prescan_charset(&html_content[..html_content.len().min(1024)])
+}
+
+/// Find the charset declared by a meta element in the search area.
+pub(crate) fn prescan_charset(search_area: &[u8]) -> Option<String> {
+    let (_, span) = next_charset_span(search_area, 0)?;
+    std::str::from_utf8(&search_area[span]).ok().map(String::from)
+}
+
+/// Find the byte ranges of the charset labels declared in an HTML document.
+/// Covers `<meta charset>`, `http-equiv` content charsets found by the prescan of the first
+/// 1024 bytes, and the encoding of a leading XML declaration.
+pub fn detect_charset_declarations(html_content: &[u8]) -> Vec<Range<usize>> {
+    charset_declarations(&html_content[..html_content.len().min(1024)])
+}
+
+/// Find the byte ranges of the charset labels declared in the search area.
+pub(crate) fn charset_declarations(search_area: &[u8]) -> Vec<Range<usize>> {
+    let mut spans: Vec<Range<usize>> = Vec::new();
+
+    if let Some(span) = xml_encoding_span(search_area) {
+        spans.push(span);
+    }
+
     let mut pos = 0;
+    while let Some((next, span)) = next_charset_span(search_area, pos) {
+        // neighbouring meta elements can report the same declaration
+        if spans.last().is_none_or(|last| span.start >= last.end) {
+            spans.push(span);
+        }
+        pos = next;
+    }
+
+    spans
+}
+
+/// Find the encoding label of an XML declaration at the start of the search area.
+fn xml_encoding_span(search_area: &[u8]) -> Option<Range<usize>> {
+    if !search_area.starts_with(b"<?xml") {
+        return None;
+    }
+    let decl_end = find_short(search_area, b"?>")?;
+    let decl = &search_area[..decl_end];
+    let enc_start = find_short(decl, b"encoding=")? + 9;
+    let (&quote, rest) = decl[enc_start..].split_first()?;
+    if quote != b'"' && quote != b'\'' {
+        return None;
+    }
+    let len = find_byte(rest, quote)?;
+    Some(enc_start + 1..enc_start + 1 + len)
+}
 
+/// Find the next charset label declared by a meta element at or after `pos`.
+/// Returns the position to resume the scan from along with the label range.
+fn next_charset_span(search_area: &[u8], mut pos: usize) -> Option<(usize, Range<usize>)> {
     while pos < search_area.len() {
         let remaining = &search_area[pos..];
-        let meta_start = match find_short(remaining, b"<meta") {
-            Some(s) => s,
-            None => break,
-        };
+        let meta_start = find_short(remaining, b"<meta")?;
+        let meta_offset = pos + meta_start;
+        // labels only count inside the meta element itself
         let meta_content = &remaining[meta_start..];
+        let meta_content = match find_byte(meta_content, b'>') {
+            Some(end) => &meta_content[..=end],
+            None => meta_content,
+        };
         pos += meta_start + 5;
 
-        // Case 1: <meta charset="...">
+        // Case 1: <meta charset="..."> or <meta charset=...>
         if let Some(charset_start) = find_short(meta_content, b"charset=") {
-            if let Some(result) = extract_charset_quoted(&meta_content[charset_start + 8..]) {
-                return Some(result);
+            if let Some((&quote, rest)) = meta_content[charset_start + 8..].split_first() {
+                if quote == b'"' || quote == b'\'' {
+                    if let Some(len) = find_byte(rest, quote) {
+                        if std::str::from_utf8(&rest[..len]).is_ok() {
+                            let start = meta_offset + charset_start + 9;
+                            return Some((pos, start..start + len));
+                        }
+                    }
+                } else if let Some(len) = meta_content[charset_start + 8..]
+                    .iter()
+                    .position(|&c| {
+                        c.is_ascii_whitespace() || matches!(c, b'>' | b'/' | b';' | b'"' | b'\'')
+                    })
+                    .filter(|&len| len > 0)
+                {
+                    let value = &meta_content[charset_start + 8..charset_start + 8 + len];
+                    if std::str::from_utf8(value).is_ok() {
+                        let start = meta_offset + charset_start + 8;
+                        return Some((pos, start..start + len));
+                    }
+                }
             }
         }
 
@@ -192,8 +613,9 @@ pub fn detect_encoding(html_content: &[u8]) -> Option<String> {
                                     .iter()
                                     .position(|&c| c == b';' || c.is_ascii_whitespace())
                                     .unwrap_or(after_cs.len());
-                                if let Ok(charset) = std::str::from_utf8(&after_cs[..cs_end]) {
-                                    return Some(charset.to_string());
+                                if std::str::from_utf8(&after_cs[..cs_end]).is_ok() {
+                                    let start = meta_offset + he_start + 25 + cs + 9 + cp + 8;
+                                    return Some((pos, start..start + cs_end));
                                 }
                             }
                         }
@@ -0,0 +1,85 @@
+use crate::error::Error;
+use encoding_rs::{EncoderResult, Encoding};
+
+/// How to handle characters the target encoding cannot represent.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnmappablePolicy {
+    /// Replace with a decimal numeric character reference like `&#12354;`, as browsers do for form submission.
+    #[default]
+    CharacterReference,
+    /// Replace with a question mark.
+    QuestionMark,
+    /// Stop and report the character.
+    Error,
+}
+
+/// Encode UTF-8 text to the encoding of the label like SHIFT_JIS.
+///
+/// UTF-16 and replacement labels encode to UTF-8, matching how browsers submit forms.
+pub fn encode_to(text: &str, label: &str, policy: UnmappablePolicy) -> Result<Vec<u8>, Error> {
+    match Encoding::for_label(label.as_bytes()) {
+        Some(encoding) => encode_with(text, encoding, policy),
+        None => Err(Error::UnknownLabel(label.to_string())),
+    }
+}
+
+/// Encode UTF-8 text to the encoding.
+pub fn encode_with(
+    text: &str,
+    encoding: &'static Encoding,
+    policy: UnmappablePolicy,
+) -> Result<Vec<u8>, Error> {
+    let mut output = Vec::new();
+    encode_into(text, encoding, policy, &mut output)?;
+    Ok(output)
+}
+
+/// Encode UTF-8 text to the encoding, appending the bytes to `output`.
+pub fn encode_into(
+    text: &str,
+    encoding: &'static Encoding,
+    policy: UnmappablePolicy,
+    output: &mut Vec<u8>,
+) -> Result<(), Error> {
+    encode_chunk(&mut encoding.new_encoder(), text, 0, true, policy, output)
+}
+
+/// Encode a chunk of text with the encoder, `offset` being the position of the chunk in the whole text.
+pub(crate) fn encode_chunk(
+    encoder: &mut encoding_rs::Encoder,
+    text: &str,
+    offset: usize,
+    last: bool,
+    policy: UnmappablePolicy,
+    output: &mut Vec<u8>,
+) -> Result<(), Error> {
+    let mut total_read = 0usize;
+
+    loop {
+        let remaining = &text[total_read..];
+        output.reserve(
+            encoder
+                .max_buffer_length_from_utf8_without_replacement(remaining.len())
+                .unwrap_or(remaining.len()),
+        );
+        let (result, read) =
+            encoder.encode_from_utf8_to_vec_without_replacement(remaining, output, last);
+        total_read += read;
+        match result {
+            EncoderResult::InputEmpty => return Ok(()),
+            EncoderResult::OutputFull => continue,
+            EncoderResult::Unmappable(character) => match policy {
+                UnmappablePolicy::CharacterReference => {
+                    output.extend_from_slice(format!("&#{};", character as u32).as_bytes())
+                }
+                UnmappablePolicy::QuestionMark => output.push(b'?'),
+                UnmappablePolicy::Error => {
+                    return Err(Error::Unmappable {
+                        character,
+                        position: offset + total_read - character.len_utf8(),
+                    })
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,58 @@
+/// The errors returned by the fallible functions of the crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The encoding label is not known.
+    UnknownLabel(String),
+    /// The input is not valid in the encoding and strict decoding was requested.
+    Malformed {
+        /// The encoding the input was decoded with.
+        encoding: &'static encoding_rs::Encoding,
+        /// The byte offset of the malformed sequence in the input.
+        position: usize,
+    },
+    /// The character can not be represented in the target encoding.
+    Unmappable {
+        /// The character that could not be encoded.
+        character: char,
+        /// The byte offset of the character in the UTF-8 text.
+        position: usize,
+    },
+    /// The encoding is not supported by the operation.
+    UnsupportedEncoding(&'static encoding_rs::Encoding),
+    /// The decoded output exceeded the configured limit in bytes or characters.
+    OutputLimit {
+        /// The configured limit.
+        limit: usize,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownLabel(label) => write!(f, "unknown encoding label: {}", label),
+            Error::Malformed { encoding, position } => write!(
+                f,
+                "malformed {} input at byte {}",
+                encoding.name(),
+                position
+            ),
+            Error::Unmappable {
+                character,
+                position,
+            } => write!(
+                f,
+                "unmappable character {:?} at byte {}",
+                character, position
+            ),
+            Error::UnsupportedEncoding(encoding) => {
+                write!(f, "unsupported encoding: {}", encoding.name())
+            }
+            Error::OutputLimit { limit } => {
+                write!(f, "decoded output exceeded the limit of {}", limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,472 @@
+use crate::meta::{FILE_TYPES_BY_EXTENSION, FILE_TYPES_BY_MIME_TYPE};
+
+/// A file format recognized by its magic number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FileType {
+    /// JPEG image.
+    Jpeg,
+    /// PNG image.
+    Png,
+    /// GIF image.
+    Gif,
+    /// BMP image.
+    Bmp,
+    /// TIFF image, little or big-endian.
+    Tiff,
+    /// BigTIFF image, little or big-endian.
+    BigTiff,
+    /// PDF document.
+    Pdf,
+    /// MP3 audio, with an ID3v2 tag or starting at a frame.
+    Mp3,
+    /// Ogg container of another codec than Opus.
+    Ogg,
+    /// FLAC audio.
+    Flac,
+    /// RIFF container of another form than WebP, WAV or AVI.
+    Riff,
+    /// WebP image in a RIFF container.
+    WebP,
+    /// WAV audio in a RIFF container.
+    Wav,
+    /// AVI video in a RIFF container.
+    Avi,
+    /// MPEG program stream or MPEG-1 video.
+    Mpeg,
+    /// MPEG transport stream.
+    MpegTs,
+    /// Matroska container.
+    Mkv,
+    /// Flash video.
+    Flv,
+    /// MP4 container.
+    Mp4,
+    /// ZIP archive.
+    Zip,
+    /// Gzip stream.
+    Gzip,
+    /// Bzip2 stream.
+    Bzip2,
+    /// Java class file.
+    JavaClass,
+    /// LHA archive.
+    Lha,
+    /// ELF executable.
+    Elf,
+    /// Tar archive in the POSIX or GNU format.
+    Tar,
+    /// ISO 9660 disc image.
+    Iso9660,
+    /// AVIF image, an AV1 image in a HEIF container.
+    Avif,
+    /// HEIC or HEIF image.
+    Heic,
+    /// JPEG XL image, as a codestream or in its container.
+    JpegXl,
+    /// Windows icon.
+    Ico,
+    /// Windows cursor.
+    Cur,
+    /// Photoshop document.
+    Psd,
+    /// QuickTime movie.
+    Mov,
+    /// WebM video, a Matroska subset.
+    WebM,
+    /// Opus audio in an Ogg container.
+    Opus,
+    /// AAC audio in ADTS frames.
+    Aac,
+    /// Standard MIDI file.
+    Midi,
+    /// RAR archive, version 4 or 5.
+    Rar,
+    /// 7z archive.
+    SevenZip,
+    /// Microsoft cabinet archive.
+    Cab,
+    /// Unix ar archive of another kind than a Debian package.
+    Ar,
+    /// Debian package.
+    Deb,
+    /// RPM package.
+    Rpm,
+    /// cpio archive in the binary, odc or newc format.
+    Cpio,
+    /// xz stream.
+    Xz,
+    /// Zstandard frame.
+    Zstd,
+    /// LZ4 frame, or a legacy LZ4 stream.
+    Lz4,
+    /// LZMA alone stream.
+    Lzma,
+    /// Unix compress stream.
+    UnixCompress,
+    /// Windows PE executable or DLL, or a DOS executable with a truncated PE header.
+    Pe,
+    /// Mach-O executable, 32 or 64-bit or a fat binary.
+    MachO,
+    /// WebAssembly module.
+    Wasm,
+    /// Android Dalvik executable.
+    Dex,
+    /// Compiled Python bytecode.
+    Pyc,
+    /// Script starting with a `#!` interpreter line. This is text, see [`FileType::is_binary`].
+    Script,
+    /// Windows installer package.
+    Msi,
+    /// Web Open Font Format.
+    Woff,
+    /// Web Open Font Format 2.
+    Woff2,
+    /// TrueType font.
+    Ttf,
+    /// OpenType font with CFF outlines.
+    Otf,
+    /// TrueType or OpenType font collection.
+    Ttc,
+    /// Embedded OpenType font.
+    Eot,
+    /// SQLite 3 database.
+    Sqlite,
+    /// Apache Parquet columnar file.
+    Parquet,
+    /// Apache Arrow IPC file.
+    Arrow,
+    /// Rich Text Format document.
+    Rtf,
+    /// PostScript document.
+    PostScript,
+    /// Encapsulated PostScript, as text or with a DOS binary header.
+    Eps,
+    /// DjVu document.
+    DjVu,
+    /// Flexible Image Transport System.
+    Fits,
+}
+
+/// The kind of content a [`FileType`] holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FileCategory {
+    /// Still or animated images.
+    Image,
+    /// Audio streams and containers.
+    Audio,
+    /// Video streams and containers.
+    Video,
+    /// Documents like PDF.
+    Document,
+    /// Archives bundling several files.
+    Archive,
+    /// Compressed streams of a single file.
+    Compressed,
+    /// Programs and bytecode.
+    Executable,
+    /// Web and desktop fonts.
+    Font,
+    /// Databases and columnar data files.
+    Database,
+    /// Formats without a more specific category.
+    Other,
+}
+
+/// How dangerous storing or serving a [`FileType`] is, ordered from least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RiskLevel {
+    /// Media and other passive content.
+    Inert,
+    /// Documents, archives and compressed streams that may carry dangerous content.
+    Low,
+    /// Bytecode and scripts run by an interpreter or virtual machine.
+    Medium,
+    /// Native executables and installers.
+    High,
+}
+
+impl FileType {
+    /// The kind of content the format holds, to filter media or archives.
+    pub fn category(self) -> FileCategory {
+        match self {
+            FileType::Jpeg => FileCategory::Image,
+            FileType::Png => FileCategory::Image,
+            FileType::Gif => FileCategory::Image,
+            FileType::Bmp => FileCategory::Image,
+            FileType::Tiff => FileCategory::Image,
+            FileType::BigTiff => FileCategory::Image,
+            FileType::Pdf => FileCategory::Document,
+            FileType::Mp3 => FileCategory::Audio,
+            FileType::Ogg => FileCategory::Audio,
+            FileType::Flac => FileCategory::Audio,
+            FileType::Riff => FileCategory::Other,
+            FileType::WebP => FileCategory::Image,
+            FileType::Wav => FileCategory::Audio,
+            FileType::Avi => FileCategory::Video,
+            FileType::Mpeg => FileCategory::Video,
+            FileType::MpegTs => FileCategory::Video,
+            FileType::Mkv => FileCategory::Video,
+            FileType::Flv => FileCategory::Video,
+            FileType::Mp4 => FileCategory::Video,
+            FileType::Zip => FileCategory::Archive,
+            FileType::Gzip => FileCategory::Compressed,
+            FileType::Bzip2 => FileCategory::Compressed,
+            FileType::JavaClass => FileCategory::Executable,
+            FileType::Lha => FileCategory::Archive,
+            FileType::Elf => FileCategory::Executable,
+            FileType::Tar => FileCategory::Archive,
+            FileType::Iso9660 => FileCategory::Archive,
+            FileType::Avif => FileCategory::Image,
+            FileType::Heic => FileCategory::Image,
+            FileType::JpegXl => FileCategory::Image,
+            FileType::Ico => FileCategory::Image,
+            FileType::Cur => FileCategory::Image,
+            FileType::Psd => FileCategory::Image,
+            FileType::Mov => FileCategory::Video,
+            FileType::WebM => FileCategory::Video,
+            FileType::Opus => FileCategory::Audio,
+            FileType::Aac => FileCategory::Audio,
+            FileType::Midi => FileCategory::Audio,
+            FileType::Rar => FileCategory::Archive,
+            FileType::SevenZip => FileCategory::Archive,
+            FileType::Cab => FileCategory::Archive,
+            FileType::Ar => FileCategory::Archive,
+            FileType::Deb => FileCategory::Archive,
+            FileType::Rpm => FileCategory::Archive,
+            FileType::Cpio => FileCategory::Archive,
+            FileType::Xz => FileCategory::Compressed,
+            FileType::Zstd => FileCategory::Compressed,
+            FileType::Lz4 => FileCategory::Compressed,
+            FileType::Lzma => FileCategory::Compressed,
+            FileType::UnixCompress => FileCategory::Compressed,
+            FileType::Pe => FileCategory::Executable,
+            FileType::MachO => FileCategory::Executable,
+            FileType::Wasm => FileCategory::Executable,
+            FileType::Dex => FileCategory::Executable,
+            FileType::Pyc => FileCategory::Executable,
+            FileType::Script => FileCategory::Executable,
+            FileType::Msi => FileCategory::Executable,
+            FileType::Woff => FileCategory::Font,
+            FileType::Woff2 => FileCategory::Font,
+            FileType::Ttf => FileCategory::Font,
+            FileType::Otf => FileCategory::Font,
+            FileType::Ttc => FileCategory::Font,
+            FileType::Eot => FileCategory::Font,
+            FileType::Sqlite => FileCategory::Database,
+            FileType::Parquet => FileCategory::Database,
+            FileType::Arrow => FileCategory::Database,
+            FileType::Rtf => FileCategory::Document,
+            FileType::PostScript => FileCategory::Document,
+            FileType::Eps => FileCategory::Document,
+            FileType::DjVu => FileCategory::Document,
+            FileType::Fits => FileCategory::Image,
+        }
+    }
+
+    /// How dangerous the format is, for crawlers deciding whether to store a download.
+    pub fn risk_level(self) -> RiskLevel {
+        match self {
+            FileType::Pe
+            | FileType::Elf
+            | FileType::MachO
+            | FileType::Msi
+            | FileType::Deb
+            | FileType::Rpm => RiskLevel::High,
+            FileType::JavaClass
+            | FileType::Wasm
+            | FileType::Dex
+            | FileType::Pyc
+            | FileType::Script => RiskLevel::Medium,
+            _ => match self.category() {
+                FileCategory::Document
+                | FileCategory::Archive
+                | FileCategory::Compressed
+                | FileCategory::Database => RiskLevel::Low,
+                _ => RiskLevel::Inert,
+            },
+        }
+    }
+
+    /// Whether the format is binary rather than text to decode, false for scripts only.
+    pub fn is_binary(self) -> bool {
+        self != FileType::Script
+    }
+
+    /// The canonical MIME type like "image/png".
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            FileType::Jpeg => "image/jpeg",
+            FileType::Png => "image/png",
+            FileType::Gif => "image/gif",
+            FileType::Bmp => "image/bmp",
+            FileType::Tiff | FileType::BigTiff => "image/tiff",
+            FileType::Pdf => "application/pdf",
+            FileType::Mp3 => "audio/mpeg",
+            FileType::Ogg => "application/ogg",
+            FileType::Flac => "audio/flac",
+            FileType::Riff => "application/octet-stream",
+            FileType::WebP => "image/webp",
+            FileType::Wav => "audio/wav",
+            FileType::Avi => "video/x-msvideo",
+            FileType::Mpeg => "video/mpeg",
+            FileType::MpegTs => "video/mp2t",
+            FileType::Mkv => "video/x-matroska",
+            FileType::Flv => "video/x-flv",
+            FileType::Mp4 => "video/mp4",
+            FileType::Zip => "application/zip",
+            FileType::Gzip => "application/gzip",
+            FileType::Bzip2 => "application/x-bzip2",
+            FileType::JavaClass => "application/java-vm",
+            FileType::Lha => "application/x-lzh-compressed",
+            FileType::Elf => "application/x-executable",
+            FileType::Tar => "application/x-tar",
+            FileType::Iso9660 => "application/x-iso9660-image",
+            FileType::Avif => "image/avif",
+            FileType::Heic => "image/heic",
+            FileType::JpegXl => "image/jxl",
+            FileType::Ico => "image/x-icon",
+            FileType::Cur => "image/x-win-bitmap",
+            FileType::Psd => "image/vnd.adobe.photoshop",
+            FileType::Mov => "video/quicktime",
+            FileType::WebM => "video/webm",
+            FileType::Opus => "audio/ogg",
+            FileType::Aac => "audio/aac",
+            FileType::Midi => "audio/midi",
+            FileType::Rar => "application/vnd.rar",
+            FileType::SevenZip => "application/x-7z-compressed",
+            FileType::Cab => "application/vnd.ms-cab-compressed",
+            FileType::Ar => "application/x-archive",
+            FileType::Deb => "application/vnd.debian.binary-package",
+            FileType::Rpm => "application/x-rpm",
+            FileType::Cpio => "application/x-cpio",
+            FileType::Xz => "application/x-xz",
+            FileType::Zstd => "application/zstd",
+            FileType::Lz4 => "application/x-lz4",
+            FileType::Lzma => "application/x-lzma",
+            FileType::UnixCompress => "application/x-compress",
+            FileType::Pe => "application/vnd.microsoft.portable-executable",
+            FileType::MachO => "application/x-mach-binary",
+            FileType::Wasm => "application/wasm",
+            FileType::Dex => "application/vnd.android.dex",
+            FileType::Pyc => "application/x-python-code",
+            FileType::Script => "application/x-shellscript",
+            FileType::Msi => "application/x-msi",
+            FileType::Woff => "font/woff",
+            FileType::Woff2 => "font/woff2",
+            FileType::Ttf => "font/ttf",
+            FileType::Otf => "font/otf",
+            FileType::Ttc => "font/collection",
+            FileType::Eot => "application/vnd.ms-fontobject",
+            FileType::Sqlite => "application/vnd.sqlite3",
+            FileType::Parquet => "application/vnd.apache.parquet",
+            FileType::Arrow => "application/vnd.apache.arrow.file",
+            FileType::Rtf => "application/rtf",
+            FileType::PostScript => "application/postscript",
+            FileType::Eps => "application/postscript",
+            FileType::DjVu => "image/vnd.djvu",
+            FileType::Fits => "image/fits",
+        }
+    }
+
+    /// The file extensions without the dot, preferred first.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            FileType::Jpeg => &["jpg", "jpeg", "jpe", "jfif"],
+            FileType::Png => &["png"],
+            FileType::Gif => &["gif"],
+            FileType::Bmp => &["bmp", "dib"],
+            FileType::Tiff | FileType::BigTiff => &["tif", "tiff"],
+            FileType::Pdf => &["pdf"],
+            FileType::Mp3 => &["mp3"],
+            FileType::Ogg => &["ogg", "oga", "ogv", "ogx"],
+            FileType::Flac => &["flac"],
+            FileType::Riff => &[],
+            FileType::WebP => &["webp"],
+            FileType::Wav => &["wav"],
+            FileType::Avi => &["avi"],
+            FileType::Mpeg => &["mpg", "mpeg", "m1v"],
+            FileType::MpegTs => &["ts", "m2ts", "mts"],
+            FileType::Mkv => &["mkv"],
+            FileType::Flv => &["flv"],
+            FileType::Mp4 => &["mp4", "m4v"],
+            FileType::Zip => &["zip"],
+            FileType::Gzip => &["gz", "tgz"],
+            FileType::Bzip2 => &["bz2", "tbz2"],
+            FileType::JavaClass => &["class"],
+            FileType::Lha => &["lzh", "lha"],
+            FileType::Elf => &["elf", "so"],
+            FileType::Tar => &["tar"],
+            FileType::Iso9660 => &["iso"],
+            FileType::Avif => &["avif"],
+            FileType::Heic => &["heic", "heif"],
+            FileType::JpegXl => &["jxl"],
+            FileType::Ico => &["ico"],
+            FileType::Cur => &["cur"],
+            FileType::Psd => &["psd", "psb"],
+            FileType::Mov => &["mov", "qt"],
+            FileType::WebM => &["webm"],
+            FileType::Opus => &["opus"],
+            FileType::Aac => &["aac"],
+            FileType::Midi => &["mid", "midi"],
+            FileType::Rar => &["rar"],
+            FileType::SevenZip => &["7z"],
+            FileType::Cab => &["cab"],
+            FileType::Ar => &["a", "ar"],
+            FileType::Deb => &["deb"],
+            FileType::Rpm => &["rpm"],
+            FileType::Cpio => &["cpio"],
+            FileType::Xz => &["xz", "txz"],
+            FileType::Zstd => &["zst"],
+            FileType::Lz4 => &["lz4"],
+            FileType::Lzma => &["lzma"],
+            FileType::UnixCompress => &["Z"],
+            FileType::Pe => &["exe", "dll", "sys"],
+            FileType::MachO => &["dylib"],
+            FileType::Wasm => &["wasm"],
+            FileType::Dex => &["dex"],
+            FileType::Pyc => &["pyc", "pyo"],
+            FileType::Script => &["sh"],
+            FileType::Msi => &["msi"],
+            FileType::Woff => &["woff"],
+            FileType::Woff2 => &["woff2"],
+            FileType::Ttf => &["ttf"],
+            FileType::Otf => &["otf"],
+            FileType::Ttc => &["ttc", "otc"],
+            FileType::Eot => &["eot"],
+            FileType::Sqlite => &["sqlite", "sqlite3", "db"],
+            FileType::Parquet => &["parquet"],
+            FileType::Arrow => &["arrow", "feather"],
+            FileType::Rtf => &["rtf"],
+            FileType::PostScript => &["ps"],
+            FileType::Eps => &["eps", "epsf"],
+            FileType::DjVu => &["djvu", "djv"],
+            FileType::Fits => &["fits", "fit", "fts"],
+        }
+    }
+
+    /// The file types content served with the MIME type like "image/png" is expected to be.
+    /// Parameters like "; charset=binary" are ignored.
+    pub fn for_mime_type(mime_type: &str) -> &'static [FileType] {
+        let essence = mime_type.split(';').next().unwrap_or_default().trim();
+        FILE_TYPES_BY_MIME_TYPE
+            .get(essence.to_ascii_lowercase().as_str())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The file types a file with the extension like "png", ".png" or "photo.png" is expected to be.
+    pub fn for_extension(extension: &str) -> &'static [FileType] {
+        let extension = extension.rsplit('.').next().unwrap_or_default();
+        FILE_TYPES_BY_EXTENSION
+            .get(extension.to_ascii_lowercase().as_str())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Whether the content starts with the signature of this file type.
+    pub fn matches(self, content: &[u8]) -> bool {
+        crate::detect::detect_file_type(content) == Some(self)
+    }
+}
@@ -56,10 +56,57 @@
 //! let language = detect_language(html_content).unwrap();
 //! println!("Language detected: {}", language);
 //! ```
+pub mod auto;
+pub mod batch;
+#[cfg(feature = "bytes")]
+pub mod bytes;
+pub mod decoder;
 pub mod detect;
+pub mod encode;
+pub mod error;
+pub mod file_type;
+pub mod limit;
+pub mod lossless;
 pub mod meta;
-pub use detect::{detect_encoding, detect_language, find_subsequence, is_binary_file};
-use encoding_rs::CoderResult;
+#[cfg(feature = "normalization")]
+pub mod normalize;
+pub mod offsets;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod rewrite;
+pub mod sniff;
+mod split;
+pub mod stream;
+pub mod transcode;
+pub use auto::{AutoEncoder, AutoEncoderBuilder, DecodeReport};
+pub use batch::{decode_batch, decode_batch_with_hints};
+#[cfg(feature = "bytes")]
+pub use crate::bytes::{auto_encode_to_bytes, encode_to_bytes};
+#[cfg(feature = "rayon")]
+pub use batch::{par_decode_batch, par_decode_batch_with_hints};
+pub use decoder::{DecodeHint, Decoder};
+pub use detect::{
+    detect_encoding, detect_file_type, detect_language, find_subsequence, is_binary_file,
+};
+pub use encode::{encode_to, UnmappablePolicy};
+pub use error::Error;
+pub use file_type::{FileCategory, FileType, RiskLevel};
+pub use limit::{LimitPolicy, OutputLimit};
+pub use lossless::{auto_decode_lossless, decode_lossless, encode_lossless, Lossless};
+#[cfg(feature = "normalization")]
+pub use normalize::{auto_encode_bytes_normalized, encode_bytes_normalized, Normalization};
+pub use offsets::{decode_with_offsets, OffsetMap};
+#[cfg(feature = "rayon")]
+pub use parallel::{par_auto_encode_bytes, par_encode_bytes};
+pub use rewrite::rewrite_declared_charset;
+pub use sniff::{classify_content, is_probably_text, ContentClass};
+pub use stream::StreamDecoder;
+pub use transcode::{transcode, Transcoder};
+#[cfg(feature = "futures")]
+pub use stream::DecodeStream;
+#[cfg(feature = "tokio")]
+pub use stream::DecodeReader;
+use encoding_rs::{CoderResult, DecoderResult};
 use meta::ENCODINGS_BY_LOCALE;
 pub extern crate encoding_rs;
 
@@ -77,106 +124,133 @@ pub fn encoding_for_locale(locale: &str) -> Option<&'static encoding_rs::Encodin
     ENCODINGS_BY_LOCALE.get(key).copied()
 }
 
-fn decode_to_string(html: &[u8], encoding: &'static encoding_rs::Encoding) -> String {
-    let mut decoder = encoding.new_decoder();
-    let mut total_read = 0usize;
-    let mut output = String::with_capacity(html.len());
-
-    let mut process = |buffer: &mut str| {
-        let mut bytes_in_buffer = 0usize;
+/// Decode the content with the encoding, appending the text to `output`.
+pub(crate) fn decode_into(
+    html: &[u8],
+    encoding: &'static encoding_rs::Encoding,
+    output: &mut String,
+) {
+    decode_chunk(&mut encoding.new_decoder(), html, true, output)
+}
 
-        loop {
-            let (result, read, written, _) = decoder.decode_to_str(
-                &html[total_read..],
-                &mut buffer[bytes_in_buffer..],
-                false,
-            );
-            total_read += read;
-            bytes_in_buffer += written;
-            match result {
-                CoderResult::InputEmpty => break,
-                CoderResult::OutputFull => {
-                    output.push_str(&buffer[..bytes_in_buffer]);
-                    bytes_in_buffer = 0;
-                }
-            }
+/// Decode a chunk straight into the spare capacity of `output`, reserving the worst case length up front.
+pub(crate) fn decode_chunk(
+    decoder: &mut encoding_rs::Decoder,
+    mut input: &[u8],
+    last: bool,
+    output: &mut String,
+) {
+    loop {
+        output.reserve(
+            decoder
+                .max_utf8_buffer_length(input.len())
+                .unwrap_or(input.len()),
+        );
+        let (result, read, _) = decoder.decode_to_string(input, output, last);
+        input = &input[read..];
+        match result {
+            CoderResult::InputEmpty => break,
+            CoderResult::OutputFull => continue,
         }
+    }
+}
 
-        loop {
-            let (result, _, written, _) =
-                decoder.decode_to_str(b"", &mut buffer[bytes_in_buffer..], true);
-            bytes_in_buffer += written;
-            output.push_str(&buffer[..bytes_in_buffer]);
-            bytes_in_buffer = 0;
-            match result {
-                CoderResult::InputEmpty => break,
-                CoderResult::OutputFull => continue,
-            }
-        }
-    };
+/// Decode a chunk without replacement, `offset` being the position of the chunk in the whole input.
+pub(crate) fn decode_strict_chunk(
+    decoder: &mut encoding_rs::Decoder,
+    input: &[u8],
+    offset: usize,
+    last: bool,
+    output: &mut String,
+) -> Result<(), Error> {
+    let mut total_read = 0usize;
 
-    match html.len() {
-        15001..=usize::MAX => {
-            let mut buf = [0u8; 8192];
-            process(std::str::from_utf8_mut(&mut buf[..]).unwrap_or_default())
-        }
-        1000..=15000 => {
-            let mut buf = [0u8; 2048];
-            process(std::str::from_utf8_mut(&mut buf[..]).unwrap_or_default())
-        }
-        _ => {
-            let mut buf = [0u8; 512];
-            process(std::str::from_utf8_mut(&mut buf[..]).unwrap_or_default())
+    loop {
+        let remaining = &input[total_read..];
+        output.reserve(
+            decoder
+                .max_utf8_buffer_length_without_replacement(remaining.len())
+                .unwrap_or(remaining.len()),
+        );
+        let (result, read) = decoder.decode_to_string_without_replacement(remaining, output, last);
+        total_read += read;
+        match result {
+            DecoderResult::InputEmpty => return Ok(()),
+            DecoderResult::OutputFull => continue,
+            DecoderResult::Malformed(bad, extra) => {
+                return Err(Error::Malformed {
+                    encoding: decoder.encoding(),
+                    position: (offset + total_read)
+                        .saturating_sub(usize::from(bad) + usize::from(extra)),
+                })
+            }
         }
     }
-
-    output
 }
 
 /// Get the content with proper encoding. Pass in a proper encoding label like SHIFT_JIS.
 pub fn encode_bytes(html: &[u8], label: &str) -> String {
-    match encoding_rs::Encoding::for_label(label.as_bytes()) {
-        Some(enc) => decode_to_string(html, enc),
-        _ => Default::default(),
+    try_encode_bytes(html, label).unwrap_or_default()
+}
+
+/// Append the content with proper encoding to `output`. Pass in a proper encoding label like SHIFT_JIS.
+pub fn encode_bytes_into(html: &[u8], label: &str, output: &mut String) {
+    let _ = AutoEncoder::default().decode_with_label_into(html, label, output);
+}
+
+/// Get the content with proper encoding, failing if the label like SHIFT_JIS is not known.
+pub fn try_encode_bytes(html: &[u8], label: &str) -> Result<String, Error> {
+    AutoEncoder::default().decode_with_label(html, label)
+}
+
+/// Get the content with proper encoding, failing on unknown labels and malformed input instead of replacing it with U+FFFD.
+pub fn try_encode_bytes_strict(html: &[u8], label: &str) -> Result<String, Error> {
+    AutoEncoder::builder()
+        .strict(true)
+        .build()
+        .decode_with_label(html, label)
+}
+
+/// Guess the encoding from the content, returning whether the guess is confident.
+pub(crate) fn guess_encoding(
+    html: &[u8],
+    tld: Option<&[u8]>,
+) -> (&'static encoding_rs::Encoding, bool) {
+    // chardetng always settles on UTF-8 for valid UTF-8 unless it sees an ISO-2022-JP escape.
+    if memchr::memchr(0x1B, html).is_none() && std::str::from_utf8(html).is_ok() {
+        return (encoding_rs::UTF_8, true);
     }
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(html, false);
+    detector.guess_assess(tld, true)
 }
 
 /// Get the content with proper encoding from a language. Pass in a proper language like "ja". This does nothing without the "encoding" flag.
 pub fn encode_bytes_from_language(html: &[u8], language: &str) -> String {
-    use encoding_rs::Encoding;
-
-    let encoding = encoding_for_locale(language)
-        .or_else(|| Encoding::for_bom(html).map(|(enc, _)| enc))
-        .unwrap_or_else(|| {
-            let mut detector = chardetng::EncodingDetector::new();
-            detector.feed(html, false);
-            detector.guess(None, true)
-        });
+    let mut output = String::new();
+    encode_bytes_from_language_into(html, language, &mut output);
+    output
+}
 
-    decode_to_string(html, encoding)
+/// Append the content with proper encoding from a language to `output`. Pass in a proper language like "ja".
+pub fn encode_bytes_from_language_into(html: &[u8], language: &str, output: &mut String) {
+    let _ = AutoEncoder::builder()
+        .trust_declared_charset(false)
+        .locale(language)
+        .build()
+        .decode_into(html, output);
 }
 
 /// Get the content with proper encoding.
 pub fn auto_encode_bytes(html: &[u8]) -> String {
-    use encoding_rs::Encoding;
-
-    if html.is_empty() {
-        return String::new();
-    }
-
-    if let Some(encoding) = detect_encoding(html) {
-        return encode_bytes(html, &encoding);
-    }
-
-    let encoding = Encoding::for_bom(html)
-        .map(|(enc, _)| enc)
-        .unwrap_or_else(|| {
-            let mut detector = chardetng::EncodingDetector::new();
-            detector.feed(html, false);
-            detector.guess(None, true)
-        });
+    let mut output = String::new();
+    auto_encode_into(html, &mut output);
+    output
+}
 
-    decode_to_string(html, encoding)
+/// Append the content with proper encoding to `output`, reusing its allocation.
+pub fn auto_encode_into(html: &[u8], output: &mut String) {
+    let _ = AutoEncoder::default().decode_into(html, output);
 }
 
 #[cfg(test)]
@@ -202,7 +276,7 @@ mod tests {
         }
         .into_string();
 
-        assert_eq!(detect_language(markup.as_bytes()).is_none(), true);
+        assert!(detect_language(markup.as_bytes()).is_none());
     }
     #[test]
     fn test_encoding_for_locale() {
@@ -250,6 +324,16 @@ mod tests {
         assert_eq!(encoded, "\u{3042}");
     }
 
+    #[test]
+    fn test_encode_bytes_large() {
+        let html_content: Vec<u8> = (0..20000).flat_map(|_| [0x82, 0xA0]).collect();
+        let encoded = encode_bytes(&html_content, "shift_jis");
+        assert_eq!(encoded, "\u{3042}".repeat(20000));
+
+        let html_content = "caf\u{e9} ".repeat(10000);
+        assert_eq!(encode_bytes(html_content.as_bytes(), "utf-8"), html_content);
+    }
+
     #[test]
     fn test_encode_bytes_from_language() {
         let html_content = b"hello";
@@ -381,35 +465,1235 @@ mod tests {
         assert!(!is_binary_file(&[]));
     }
 
+    #[test]
+    fn test_detect_file_type() {
+        let cases: &[(&[u8], Option<FileType>)] = &[
+            (&[0xFF, 0xD8, 0xFF, 0xE0], Some(FileType::Jpeg)),
+            (&[0xFF, 0xFB, 0x90], Some(FileType::Mp3)),
+            (b"ID3\x04", Some(FileType::Mp3)),
+            (&[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A], Some(FileType::Png)),
+            (b"%PDF-1.7", Some(FileType::Pdf)),
+            (b"BZh91AY", Some(FileType::Bzip2)),
+            (b"BM\x36\x00", Some(FileType::Bmp)),
+            (&[0x4D, 0x4D, 0x00, 0x2B], Some(FileType::BigTiff)),
+            (&[0x50, 0x4B, 0x03, 0x04], Some(FileType::Zip)),
+            (&[0x1F, 0x8B, 0x08], Some(FileType::Gzip)),
+            (&[0x7F, 0x45, 0x4C, 0x46, 0x02], Some(FileType::Elf)),
+            (&[0xFF, 0xD8], None),
+            (b"<html>", None),
+            (&[], None),
+        ];
+        for &(content, expected) in cases {
+            assert_eq!(detect_file_type(content), expected, "{:?}", content);
+            assert_eq!(is_binary_file(content), expected.is_some());
+        }
+    }
+
+    #[test]
+    fn test_file_type_mime_types() {
+        assert_eq!(FileType::Png.mime_type(), "image/png");
+        assert_eq!(FileType::Jpeg.extensions()[0], "jpg");
+        assert_eq!(FileType::for_mime_type("IMAGE/PNG"), &[FileType::Png]);
+        assert_eq!(
+            FileType::for_mime_type("image/tiff; charset=binary"),
+            &[FileType::Tiff, FileType::BigTiff]
+        );
+        assert_eq!(FileType::for_extension(".JPG"), &[FileType::Jpeg]);
+        assert_eq!(FileType::for_extension("archive.tar.gz"), &[FileType::Gzip]);
+        assert!(FileType::for_mime_type("text/html").is_empty());
+        assert!(FileType::for_extension("html").is_empty());
+        assert!(FileType::Png.matches(&[0x89, 0x50, 0x4E, 0x47]));
+        assert!(!FileType::Png.matches(&[0xFF, 0xD8, 0xFF]));
+
+        let detected: &[(&[u8], FileType)] = &[
+            (&[0xFF, 0xD8, 0xFF], FileType::Jpeg),
+            (&[0x89, 0x50, 0x4E, 0x47], FileType::Png),
+            (b"GIF8", FileType::Gif),
+            (b"BM", FileType::Bmp),
+            (&[0x49, 0x49, 0x2A, 0x00], FileType::Tiff),
+            (&[0x49, 0x49, 0x2B, 0x00], FileType::BigTiff),
+            (b"%PDF", FileType::Pdf),
+            (b"ID3", FileType::Mp3),
+            (b"OggS", FileType::Ogg),
+            (b"fLaC", FileType::Flac),
+            (&[0x00, 0x00, 0x01, 0xBA, 0x44], FileType::Mpeg),
+            (&[0x1A, 0x45, 0xDF, 0xA3], FileType::Mkv),
+            (&[0x46, 0x4C, 0x56, 0x01], FileType::Flv),
+            (b"\x00\x00\x00\x18ftypmp42", FileType::Mp4),
+            (&[0x50, 0x4B, 0x03, 0x04], FileType::Zip),
+            (&[0x1F, 0x8B], FileType::Gzip),
+            (b"BZh", FileType::Bzip2),
+            (&[0xCA, 0xFE, 0xBA, 0xBE], FileType::JavaClass),
+            (&[0x7F, 0x45, 0x4C, 0x46], FileType::Elf),
+        ];
+        for &(content, file_type) in detected {
+            assert_eq!(detect_file_type(content), Some(file_type));
+            assert!(FileType::for_mime_type(file_type.mime_type()).contains(&file_type));
+            for extension in file_type.extensions() {
+                assert!(FileType::for_extension(extension).contains(&file_type));
+            }
+        }
+    }
+
+    #[test]
+    fn test_detect_file_type_at_offset() {
+        let mut tar = vec![0u8; 512];
+        tar[..8].copy_from_slice(b"file.txt");
+        tar[257..265].copy_from_slice(b"ustar\x0000");
+        assert_eq!(detect_file_type(&tar), Some(FileType::Tar));
+        tar[257..265].copy_from_slice(b"ustar  \x00");
+        assert_eq!(detect_file_type(&tar), Some(FileType::Tar));
+        tar[263..265].copy_from_slice(b"99");
+        assert_eq!(detect_file_type(&tar), None);
+
+        let mut iso = vec![0u8; 40 * 1024];
+        iso[32768..32774].copy_from_slice(b"\x01CD001");
+        assert_eq!(detect_file_type(&iso), Some(FileType::Iso9660));
+        assert_eq!(detect_file_type(&iso[..32770]), None);
+
+        assert_eq!(
+            detect_file_type(b"\x00\x00\x00\x20ftypisom\x00\x00\x02\x00"),
+            Some(FileType::Mp4)
+        );
+        assert_eq!(detect_file_type(&[0x00, 0x00, 0x00, 0x18]), None);
+    }
+
+    #[test]
+    fn test_detect_lha() {
+        let mut level0 = vec![0u8; 64];
+        level0[0] = 30;
+        level0[2..7].copy_from_slice(b"-lh5-");
+        assert_eq!(detect_file_type(&level0), Some(FileType::Lha));
+
+        let mut level2 = level0.clone();
+        level2[..2].copy_from_slice(&40u16.to_le_bytes());
+        level2[2..7].copy_from_slice(b"-lhd-");
+        level2[20] = 2;
+        assert_eq!(detect_file_type(&level2), Some(FileType::Lha));
+
+        level0[0] = 4;
+        assert_eq!(detect_file_type(&level0), None);
+        level0[0] = 30;
+        level0[2..7].copy_from_slice(b"-lh9-");
+        assert_eq!(detect_file_type(&level0), None);
+        assert_eq!(detect_file_type(b"\x1e\x00-lz5-"), None);
+    }
+
+    #[test]
+    fn test_text_is_not_binary() {
+        let prefixes: &[&str] = &[
+            "Lorem ipsum dolor sit amet, consectetur adipiscing elit.",
+            "LICENSE\n\nPermission is hereby granted, free of charge",
+            "Last updated: 2024-01-01",
+            "L",
+            "Licensed under the Apache License, Version 2.0",
+            "<!DOCTYPE html><html lang=\"en\"><head>",
+            "<html><body>Hello</body></html>",
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>",
+            "{\"name\": \"auto_encoder\", \"version\": \"0.2.3\"}",
+            "[package]\nname = \"auto_encoder\"",
+            "# Auto Encoder\n\nDetect encodings",
+            "// Copyright 2024 The Authors",
+            "/* comment */ body { margin: 0 }",
+            "function main() { return 0; }",
+            "import os\nprint('hello')",
+            "MIT License\n\nCopyright (c) 2024",
+            "Dear Sir or Madam,",
+            "GET / HTTP/1.1\r\nHost: example.com",
+            "id,name,email\n1,Jane,jane@example.com",
+            "\u{feff}Title with a byte order mark",
+            "-----BEGIN CERTIFICATE-----",
+            "    indented text",
+            "1. First item\n2. Second item",
+            "The wide world of sports",
+            "How wide is it?",
+            "The moov atom",
+            "Somemoov",
+            "A\r\r\nline",
+        ];
+        for prefix in prefixes {
+            assert!(!is_binary_file(prefix.as_bytes()), "{:?}", prefix);
+            assert_eq!(detect_file_type(prefix.as_bytes()), None, "{:?}", prefix);
+        }
+
+        // scripts are recognized but still count as text
+        let script = b"#!/usr/bin/env python3 -- a script in a text editor is still text";
+        assert!(!is_binary_file(script));
+        assert_eq!(detect_file_type(script), Some(FileType::Script));
+    }
+
+    #[test]
+    fn test_detect_masked_signatures() {
+        let riff = |form: &[u8]| [b"RIFF\x24\x08\x00\x00".as_slice(), form].concat();
+        assert_eq!(detect_file_type(&riff(b"WEBPVP8 ")), Some(FileType::WebP));
+        assert_eq!(detect_file_type(&riff(b"WAVEfmt ")), Some(FileType::Wav));
+        assert_eq!(detect_file_type(&riff(b"AVI LIST")), Some(FileType::Avi));
+        assert_eq!(detect_file_type(&riff(b"CDXAfmt ")), Some(FileType::Riff));
+
+        // MPEG-2 and MPEG-1 pack headers differ in the marker bits after the start code
+        assert_eq!(
+            detect_file_type(&[0x00, 0x00, 0x01, 0xBA, 0x44, 0x00]),
+            Some(FileType::Mpeg)
+        );
+        assert_eq!(
+            detect_file_type(&[0x00, 0x00, 0x01, 0xBA, 0x21, 0x00]),
+            Some(FileType::Mpeg)
+        );
+        assert_eq!(detect_file_type(&[0x00, 0x00, 0x01, 0xBA, 0x00]), None);
+
+        let mut ts = vec![0xFFu8; 188 * 4];
+        for packet in ts.chunks_mut(188) {
+            packet[..4].copy_from_slice(&[0x47, 0x40, 0x11, 0x10]);
+        }
+        assert_eq!(detect_file_type(&ts), Some(FileType::MpegTs));
+        ts[1] = 0xC0;
+        assert_eq!(detect_file_type(&ts), None);
+        assert_eq!(detect_file_type(&ts[..188 * 3]), None);
+        assert_eq!(detect_file_type(b"GIF89a"), Some(FileType::Gif));
+        assert!(!is_binary_file("Good morning, ".repeat(60).as_bytes()));
+    }
+
+    /// Check that each case is detected as its file type with the expected property, and that
+    /// the type round trips through its mime type and extensions.
+    fn assert_detected<T: PartialEq + std::fmt::Debug>(
+        cases: &[(&[u8], FileType, T)],
+        property: fn(FileType) -> T,
+    ) {
+        for (content, file_type, expected) in cases {
+            assert_eq!(detect_file_type(content), Some(*file_type), "{:?}", content);
+            assert_eq!(&property(*file_type), expected, "{:?}", file_type);
+            assert_eq!(is_binary_file(content), file_type.is_binary());
+            assert!(FileType::for_mime_type(file_type.mime_type()).contains(file_type));
+            for extension in file_type.extensions() {
+                assert!(FileType::for_extension(extension).contains(file_type));
+            }
+        }
+    }
+
+    #[test]
+    fn test_detect_media_signatures() {
+        let ftyp = |brand: &[u8]| {
+            [b"\x00\x00\x00\x1cftyp".as_slice(), brand, b"\x00\x00\x00\x00"].concat()
+        };
+        let mut opus = b"OggS\x00\x02".to_vec();
+        opus.resize(28, 0);
+        opus.extend_from_slice(b"OpusHead\x01\x02");
+        let webm = b"\x1a\x45\xdf\xa3\x9f\x42\x86\x81\x01\x42\xf7\x81\x01\x42\x82\x84webm\x42\x87";
+        let matroska = b"\x1a\x45\xdf\xa3\xa3\x42\x86\x81\x01\x42\x82\x88matroska\x42\x87";
+
+        let cases: &[(&[u8], FileType, FileCategory)] = &[
+            (&ftyp(b"avif"), FileType::Avif, FileCategory::Image),
+            (&ftyp(b"heic"), FileType::Heic, FileCategory::Image),
+            (&ftyp(b"mif1"), FileType::Heic, FileCategory::Image),
+            (b"\x00\x00\x00\x1cftypmif1\x00\x00\x00\x00mif1avifmiaf", FileType::Avif, FileCategory::Image),
+            (b"\x00\x00\x00\x14ftypmif1\x00\x00\x00\x00mif1avif", FileType::Heic, FileCategory::Image),
+            (&ftyp(b"qt  "), FileType::Mov, FileCategory::Video),
+            (&ftyp(b"isom"), FileType::Mp4, FileCategory::Video),
+            (b"\x00\x00\x00\x08wide\x00\x00\x00\x00mdat", FileType::Mov, FileCategory::Video),
+            (b"\x00\x00\x01\x2Amoov\x00\x00\x00\x6Cmvhd", FileType::Mov, FileCategory::Video),
+            (&[0xFF, 0x0A, 0xFA, 0x7F], FileType::JpegXl, FileCategory::Image),
+            (b"\x00\x00\x00\x0cJXL \x0d\x0a\x87\x0a", FileType::JpegXl, FileCategory::Image),
+            (b"\x00\x00\x01\x00\x01\x00\x10\x10\x00\x00", FileType::Ico, FileCategory::Image),
+            (b"\x00\x00\x02\x00\x01\x00\x20\x20\x00\x00", FileType::Cur, FileCategory::Image),
+            (b"8BPS\x00\x01", FileType::Psd, FileCategory::Image),
+            (webm, FileType::WebM, FileCategory::Video),
+            (matroska, FileType::Mkv, FileCategory::Video),
+            (&opus, FileType::Opus, FileCategory::Audio),
+            (b"OggS\x00\x02", FileType::Ogg, FileCategory::Audio),
+            (&[0xFF, 0xF1, 0x50, 0x80], FileType::Aac, FileCategory::Audio),
+            (&[0xFF, 0xF9, 0x50, 0x80], FileType::Aac, FileCategory::Audio),
+            (&[0xFF, 0xF3, 0x90, 0x64], FileType::Mp3, FileCategory::Audio),
+            (&[0xFF, 0xF2, 0x90, 0x64], FileType::Mp3, FileCategory::Audio),
+            (&[0xFF, 0xFB, 0x90, 0x64], FileType::Mp3, FileCategory::Audio),
+            (b"MThd\x00\x00\x00\x06\x00\x01", FileType::Midi, FileCategory::Audio),
+            (&[0xFF, 0xD8, 0xFF, 0xE0], FileType::Jpeg, FileCategory::Image),
+        ];
+        assert_detected(cases, FileType::category);
+
+        assert_eq!(detect_file_type(b"\x00\x00\x01\x00\x00\x00\x10\x10\x00\x00"), None);
+        assert_eq!(detect_file_type(b"MThd is not a MIDI header"), None);
+        assert_eq!(FileType::for_mime_type("audio/ogg"), &[FileType::Ogg, FileType::Opus]);
+    }
+
+    #[test]
+    fn test_detect_archive_signatures() {
+        let mut newc = b"070701".to_vec();
+        newc.extend_from_slice(&[b'0'; 104]);
+        let mut odc = b"070707".to_vec();
+        odc.extend_from_slice(&[b'7'; 70]);
+        let mut lzma = vec![0x5D, 0x00, 0x00, 0x80, 0x00];
+        lzma.extend_from_slice(&[0xFF; 8]);
+
+        let cases: &[(&[u8], FileType, FileCategory)] = &[
+            (b"Rar!\x1a\x07\x00\xcf\x90", FileType::Rar, FileCategory::Archive),
+            (b"Rar!\x1a\x07\x01\x00\x33", FileType::Rar, FileCategory::Archive),
+            (b"7z\xbc\xaf\x27\x1c\x00\x04", FileType::SevenZip, FileCategory::Archive),
+            (b"MSCF\x00\x00\x00\x00\x1b\x00", FileType::Cab, FileCategory::Archive),
+            (b"!<arch>\ndebian-binary   ", FileType::Deb, FileCategory::Archive),
+            (b"!<arch>\nlibfoo.o/       ", FileType::Ar, FileCategory::Archive),
+            (&[0xED, 0xAB, 0xEE, 0xDB, 0x03, 0x00], FileType::Rpm, FileCategory::Archive),
+            (&newc, FileType::Cpio, FileCategory::Archive),
+            (&odc, FileType::Cpio, FileCategory::Archive),
+            (&[0xC7, 0x71, 0x00, 0x00], FileType::Cpio, FileCategory::Archive),
+            (b"PK\x05\x06\x00\x00", FileType::Zip, FileCategory::Archive),
+            (b"\xfd7zXZ\x00\x00\x04", FileType::Xz, FileCategory::Compressed),
+            (&[0x28, 0xB5, 0x2F, 0xFD, 0x24], FileType::Zstd, FileCategory::Compressed),
+            (&[0x04, 0x22, 0x4D, 0x18, 0x64], FileType::Lz4, FileCategory::Compressed),
+            (&lzma, FileType::Lzma, FileCategory::Compressed),
+            (&[0x1F, 0x9D, 0x90], FileType::UnixCompress, FileCategory::Compressed),
+            (&[0x1F, 0x8B, 0x08], FileType::Gzip, FileCategory::Compressed),
+        ];
+        assert_detected(cases, FileType::category);
+
+        assert_eq!(detect_file_type(b"070701 is a phone number"), None);
+        lzma[3] = 0x81;
+        assert_eq!(detect_file_type(&lzma), None);
+        assert_eq!(FileType::for_extension("backup.Z"), &[FileType::UnixCompress]);
+    }
+
+    #[test]
+    fn test_detect_executables() {
+        let mut pe = vec![0u8; 0x100];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x18] = 0x40;
+        pe[0x3C] = 0x80;
+        pe[0x80..0x84].copy_from_slice(b"PE\x00\x00");
+        let mut msi = vec![0u8; 1024];
+        msi[..8].copy_from_slice(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]);
+        msi[0x1E] = 9;
+        msi[512 + 0x50..512 + 0x60].copy_from_slice(&[
+            0x84, 0x10, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x46,
+        ]);
+
+        let cases: &[(&[u8], FileType, RiskLevel)] = &[
+            (&pe, FileType::Pe, RiskLevel::High),
+            (&pe[..0x60], FileType::Pe, RiskLevel::High),
+            (&msi, FileType::Msi, RiskLevel::High),
+            (&[0xCF, 0xFA, 0xED, 0xFE, 0x07], FileType::MachO, RiskLevel::High),
+            (&[0xFE, 0xED, 0xFA, 0xCE, 0x00], FileType::MachO, RiskLevel::High),
+            (&[0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x02], FileType::MachO, RiskLevel::High),
+            (&[0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x41], FileType::JavaClass, RiskLevel::Medium),
+            (&[0x7F, 0x45, 0x4C, 0x46, 0x02], FileType::Elf, RiskLevel::High),
+            (b"\x00asm\x01\x00\x00\x00\x01", FileType::Wasm, RiskLevel::Medium),
+            (b"dex\n035\x00", FileType::Dex, RiskLevel::Medium),
+            (&[0xA7, 0x0D, 0x0D, 0x0A, 0x00, 0x00, 0x00, 0x00], FileType::Pyc, RiskLevel::Medium),
+            (&[0x03, 0xF3, 0x0D, 0x0A, 0x6B, 0x2C, 0x5E, 0x66], FileType::Pyc, RiskLevel::Medium),
+            (b"#!/bin/sh\necho hi", FileType::Script, RiskLevel::Medium),
+            (b"#! /usr/bin/perl", FileType::Script, RiskLevel::Medium),
+            (b"Rar!\x1a\x07\x00", FileType::Rar, RiskLevel::Low),
+            (&[0x89, 0x50, 0x4E, 0x47], FileType::Png, RiskLevel::Inert),
+        ];
+        assert_detected(cases, FileType::risk_level);
+
+        assert!(RiskLevel::High > RiskLevel::Medium);
+        assert_eq!(FileType::Pe.category(), FileCategory::Executable);
+        assert_eq!(detect_file_type(b"MZ is not an executable"), None);
+        pe[0x80] = b'X';
+        assert_eq!(detect_file_type(&pe), None);
+        msi[512 + 0x50] = 0;
+        assert_eq!(detect_file_type(&msi), None);
+        assert_eq!(detect_file_type(b"Hi\r\nthere"), None);
+    }
+
+    #[test]
+    fn test_detect_font_and_data_signatures() {
+        let mut eot = vec![0u8; 40];
+        eot[8..12].copy_from_slice(&0x0002_0001u32.to_le_bytes());
+        eot[34..36].copy_from_slice(b"LP");
+
+        let cases: &[(&[u8], FileType, FileCategory)] = &[
+            (b"wOFF\x00\x01\x00\x00", FileType::Woff, FileCategory::Font),
+            (b"wOF2\x00\x01\x00\x00", FileType::Woff2, FileCategory::Font),
+            (b"\x00\x01\x00\x00\x00\x0c\x00\x80\x00\x03", FileType::Ttf, FileCategory::Font),
+            (b"true\x00\x10\x01\x00\x00\x04", FileType::Ttf, FileCategory::Font),
+            (b"OTTO\x00\x09\x00\x80\x00\x03", FileType::Otf, FileCategory::Font),
+            (b"ttcf\x00\x02\x00\x00\x00\x00\x00\x02", FileType::Ttc, FileCategory::Font),
+            (&eot, FileType::Eot, FileCategory::Font),
+            (b"SQLite format 3\x00\x10\x00", FileType::Sqlite, FileCategory::Database),
+            (b"PAR1\x15\x04", FileType::Parquet, FileCategory::Database),
+            (b"ARROW1\x00\x00\xff\xff", FileType::Arrow, FileCategory::Database),
+            (b"{\\rtf1\\ansi\\deff0", FileType::Rtf, FileCategory::Document),
+            (b"%!PS-Adobe-3.0\n%%Title: x", FileType::PostScript, FileCategory::Document),
+            (b"%!PS-Adobe-3.0 EPSF-3.0\n", FileType::Eps, FileCategory::Document),
+            (&[0xC5, 0xD0, 0xD3, 0xC6, 0x20], FileType::Eps, FileCategory::Document),
+            (b"AT&TFORM\x00\x00\x10\x00DJVUINFO", FileType::DjVu, FileCategory::Document),
+            (b"SIMPLE  =                    T / conforms", FileType::Fits, FileCategory::Image),
+        ];
+        assert_detected(cases, FileType::category);
+
+        assert_eq!(FileType::Sqlite.risk_level(), RiskLevel::Low);
+        assert_eq!(FileType::Woff2.risk_level(), RiskLevel::Inert);
+        // text that merely starts like a font or FITS header
+        assert_eq!(detect_file_type(b"true, false"), None);
+        assert_eq!(detect_file_type(b"OTTO the dog"), None);
+        assert_eq!(detect_file_type(b"SIMPLE  = F"), None);
+        eot[8] = 0xFF;
+        assert_eq!(detect_file_type(&eot), None);
+    }
+
+    #[test]
+    fn test_classify_content() {
+        use encoding_rs::{UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+        let text = "Hello, wörld!\r\n\tIndented line\n";
+        let utf16 = |encoding: &'static encoding_rs::Encoding| -> Vec<u8> {
+            text.encode_utf16()
+                .flat_map(|unit| {
+                    if encoding == UTF_16BE {
+                        unit.to_be_bytes()
+                    } else {
+                        unit.to_le_bytes()
+                    }
+                })
+                .collect()
+        };
+        let (latin1, _, _) = WINDOWS_1252.encode("Café crème brûlée");
+
+        assert_eq!(classify_content(text.as_bytes()), ContentClass::Text(Some(UTF_8)));
+        assert_eq!(classify_content(&utf16(UTF_16LE)), ContentClass::Text(Some(UTF_16LE)));
+        assert_eq!(classify_content(&utf16(UTF_16BE)), ContentClass::Text(Some(UTF_16BE)));
+        assert_eq!(
+            classify_content(&[b"\xFF\xFE".as_slice(), &utf16(UTF_16LE)].concat()),
+            ContentClass::Text(Some(UTF_16LE))
+        );
+        assert_eq!(classify_content(&latin1), ContentClass::Text(None));
+        assert_eq!(classify_content(b"\x1b[31mred\x1b[0m\n"), ContentClass::Text(Some(UTF_8)));
+        assert_eq!(classify_content(&[0x89, 0x50, 0x4E, 0x47]), ContentClass::Known(FileType::Png));
+        assert!(is_probably_text(b"#!/bin/sh\necho hi"));
+        assert!(!is_probably_text(&[0x89, 0x50, 0x4E, 0x47]));
+
+        // protobuf and other unknown formats
+        assert_eq!(classify_content(b"\x08\x96\x01\x12\x07testing\x1a\x03\x08\x96\x01"), ContentClass::Binary);
+        assert_eq!(classify_content(b"\x00\x00\x00\x2a\x00\x10\xff\x01"), ContentClass::Binary);
+        assert!(!is_binary_file(b"\x08\x96\x01\x12\x07testing"));
+
+        // CJK text has null bytes on both sides or none at all
+        let cjk = |text: &str, big_endian: bool| -> Vec<u8> {
+            text.encode_utf16()
+                .flat_map(|unit| if big_endian { unit.to_be_bytes() } else { unit.to_le_bytes() })
+                .collect()
+        };
+        for text in [
+            "\u{4e00}\u{4e2a}\u{6d4b}\u{8bd5} \u{4e00}\u{4e8c}\u{4e09} hello \u{4e16}\u{754c}",
+            "\u{6d4b}\u{8bd5}\u{6587}\u{672c}\u{4e16}\u{754c}\u{4e2d}\u{6587}",
+            "\u{d55c}\u{ad6d}\u{c5b4} \u{d14c}\u{c2a4}\u{d2b8}\u{c785}\u{b2c8}\u{b2e4}.",
+            "\u{65e5}\u{672c}\u{8a9e}\u{306e}\u{30c6}\u{30ad}\u{30b9}\u{30c8}\u{3067}\u{3059}\u{3002}",
+        ] {
+            assert_eq!(classify_content(&cjk(text, false)), ContentClass::Text(Some(UTF_16LE)), "{}", text);
+            assert_eq!(classify_content(&cjk(text, true)), ContentClass::Text(Some(UTF_16BE)), "{}", text);
+        }
+
+        // a lone surrogate is not UTF-16 text
+        assert_eq!(classify_content(b"a\x00b\x00\x00\xdcc\x00"), ContentClass::Binary);
+
+        // one stray control byte in a long text is tolerated, several are not
+        let mut long = "text ".repeat(100).into_bytes();
+        long[10] = 0x01;
+        assert!(is_probably_text(&long));
+        long[20..30].fill(0x02);
+        assert!(!is_probably_text(&long));
+
+        // a character cut at the end of the sample is still UTF-8
+        let mut cut = "a".repeat(sniff::SNIFF_LEN - 1).into_bytes();
+        cut.extend_from_slice("é and more".as_bytes());
+        assert_eq!(classify_content(&cut), ContentClass::Text(Some(UTF_8)));
+        assert!(is_probably_text(b""));
+    }
+
+    #[test]
+    fn test_magic_table_sorted() {
+        assert!(detect::MAGIC_TABLE.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
     #[test]
     fn test_binary_detection_parity() {
-        // Verify flattened MAGIC_TABLE matches PHF-based detection
-        let test_cases: &[&[u8]] = &[
-            &[0xFF, 0xD8, 0xFF],       // JPEG
-            &[0x89, 0x50, 0x4E, 0x47], // PNG
-            &[0x47, 0x49, 0x46, 0x38], // GIF
-            &[0x42, 0x5A, 0x68],       // BZip
-            &[0x42, 0x4D, 0x00],       // BMP
-            &[0x50, 0x4B, 0x03, 0x04], // ZIP
-            &[0x1F, 0x8B],             // GZIP
-            &[0x7F, 0x45, 0x4C, 0x46], // ELF
-            &[0xCA, 0xFE, 0xBA, 0xBE], // Java class
-            &[0x25, 0x50, 0x44, 0x46], // PDF
-            &[0x00, 0x00, 0x01, 0xBA], // MPEG
-            &[0xFF, 0xFB],             // MP3
-            &[0x49, 0x44, 0x33],       // MP3 ID3
-            &[0x00, 0x00, 0x00, 0x00], // No match
-            &[0x01, 0x02, 0x03],       // No match
-            &[],                        // Empty
+        // is_binary_file_phf is kept as an alias of the signature table
+        let test_cases: &[(&[u8], bool)] = &[
+            (&[0xFF, 0xD8, 0xFF], true),             // JPEG
+            (&[0x89, 0x50, 0x4E, 0x47], true),       // PNG
+            (&[0x47, 0x49, 0x46, 0x38], true),       // GIF
+            (&[0x42, 0x5A, 0x68], true),             // BZip
+            (&[0x42, 0x4D, 0x00], true),             // BMP
+            (&[0x50, 0x4B, 0x03, 0x04], true),       // ZIP
+            (&[0x1F, 0x8B], true),                   // GZIP
+            (&[0x7F, 0x45, 0x4C, 0x46], true),       // ELF
+            (&[0xCA, 0xFE, 0xBA, 0xBE], true),       // Java class
+            (&[0x25, 0x50, 0x44, 0x46], true),       // PDF
+            (&[0x00, 0x00, 0x01, 0xBA], false),      // MPEG pack start code without the header
+            (&[0x00, 0x00, 0x01, 0xBA, 0x44], true), // MPEG-2 pack header
+            (&[0x00, 0x00, 0x01, 0xBA, 0x00], false), // Neither MPEG-1 nor MPEG-2
+            (&[0x00, 0x00, 0x00, 0x18], false),      // MP4 box size without ftyp
+            (b"RIFF\x24\x08\x00\x00WAVE", true),    // RIFF form
+            (&[0xFF, 0xFB], true),                   // MP3
+            (&[0x49, 0x44, 0x33], true),             // MP3 ID3
+            (b"Rar!\x1a\x07\x00", true),            // RAR
+            (b"7z\xbc\xaf\x27\x1c", true),          // 7z
+            (&[0x00, 0x00, 0x00, 0x00], false),      // No match
+            (&[0x01, 0x02, 0x03], false),            // No match
+            (b"Lorem", false),                       // Text starting with L
+            (b"LICENSE", false),                     // Text starting with L
+            (&[], false),                            // Empty
+        ];
+        for &(case, expected) in test_cases {
+            assert_eq!(is_binary_file(case), expected, "Mismatch for {:?}", case);
+            assert_eq!(detect::is_binary_file_phf(case), expected, "Mismatch for {:?}", case);
+        }
+    }
+
+    #[test]
+    fn test_auto_encode_into_appends() {
+        let mut output = String::from("prefix:");
+        auto_encode_into(b"\xa1Hola!", &mut output);
+        assert_eq!(output, format!("prefix:{}", auto_encode_bytes(b"\xa1Hola!")));
+    }
+
+    #[test]
+    fn test_sniff_encoding_utf8_fast_path() {
+        let docs: &[&[u8]] = &[
+            b"plain ascii",
+            "caf\u{e9} \u{3053}\u{3093}".as_bytes(),
+            b"\x1B$B$3$s\x1B(B",
+            b"\xCF\xF0\xE8\xE2\xE5\xF2",
         ];
-        for case in test_cases {
+        for doc in docs {
+            let mut detector = chardetng::EncodingDetector::new();
+            detector.feed(doc, false);
+            assert_eq!(guess_encoding(doc, None).0, detector.guess(None, true));
+        }
+    }
+
+    #[test]
+    fn test_decoder_reuses_buffer() {
+        let mut decoder = Decoder::with_capacity(64);
+        let capacity = decoder.capacity();
+        assert_eq!(decoder.decode(b"\xa1Hola!"), auto_encode_bytes(b"\xa1Hola!"));
+        assert_eq!(decoder.decode_with_label(b"\x82\xA0", "shift_jis"), "\u{3042}");
+        assert_eq!(decoder.decode_with_language(b"hello", "en-us"), "hello");
+        assert_eq!(decoder.capacity(), capacity);
+        assert_eq!(decoder.take(), "hello");
+        assert_eq!(decoder.as_str(), "");
+    }
+
+    #[test]
+    fn test_encode_to() {
+        assert_eq!(
+            encode_to("\u{3042}a", "shift_jis", UnmappablePolicy::Error).unwrap(),
+            b"\x82\xA0a"
+        );
+        assert_eq!(
+            encode_to("\u{a1}Hola!", "windows-1252", UnmappablePolicy::Error).unwrap(),
+            b"\xa1Hola!"
+        );
+        assert_eq!(
+            encode_to("caf\u{e9}", "utf-16le", UnmappablePolicy::Error).unwrap(),
+            "caf\u{e9}".as_bytes()
+        );
+        assert_eq!(
+            encode_to("x", "shiftjis", UnmappablePolicy::Error),
+            Err(Error::UnknownLabel("shiftjis".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_encode_to_unmappable_policies() {
+        let text = "a\u{3042}b\u{1F600}";
+        assert_eq!(
+            encode_to(text, "windows-1252", UnmappablePolicy::CharacterReference).unwrap(),
+            b"a&#12354;b&#128512;"
+        );
+        assert_eq!(
+            encode_to(text, "windows-1252", UnmappablePolicy::QuestionMark).unwrap(),
+            b"a?b?"
+        );
+        assert_eq!(
+            encode_to(text, "windows-1252", UnmappablePolicy::Error),
+            Err(Error::Unmappable {
+                character: '\u{3042}',
+                position: 1
+            })
+        );
+        assert_eq!(
+            encode_to("\u{3042}\u{e9}", "iso-2022-jp", UnmappablePolicy::CharacterReference)
+                .unwrap(),
+            b"\x1B$B$\"\x1B(B&#233;"
+        );
+    }
+
+    #[test]
+    fn test_transcode() {
+        assert_eq!(
+            transcode(b"\x82\xA0a", "shift_jis", "euc-jp", UnmappablePolicy::Error).unwrap(),
+            b"\xA4\xA2a"
+        );
+        assert_eq!(
+            transcode(b"\xCF\xF0\xE8", "windows-1251", "koi8-r", UnmappablePolicy::Error)
+                .unwrap(),
+            b"\xF0\xD2\xC9"
+        );
+        assert_eq!(
+            transcode(b"\x82\xA0", "shift_jis", "windows-1252", UnmappablePolicy::Error),
+            Err(Error::Unmappable {
+                character: '\u{3042}',
+                position: 0
+            })
+        );
+        assert_eq!(
+            transcode(b"x", "sjis", "koi9", UnmappablePolicy::Error),
+            Err(Error::UnknownLabel("koi9".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_transcoder_chunks() {
+        let input: Vec<u8> = (0..5000).flat_map(|_| [0x82, 0xA0, b'x']).collect();
+        let expected = transcode(&input, "shift_jis", "euc-jp", UnmappablePolicy::Error).unwrap();
+        let mut transcoder = Transcoder::new(
+            encoding_rs::SHIFT_JIS,
+            encoding_rs::EUC_JP,
+            UnmappablePolicy::Error,
+        );
+        let mut output = Vec::new();
+        for chunk in input.chunks(333) {
+            transcoder.push(chunk, false, &mut output).unwrap();
+        }
+        transcoder.push(b"", true, &mut output).unwrap();
+        assert_eq!(output, expected);
+        assert_eq!(
+            encode_bytes(&output, "euc-jp"),
+            encode_bytes(&input, "shift_jis")
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_decode_matches_encode_bytes() {
+        let mut sjis: Vec<u8> = Vec::new();
+        let mut gb18030: Vec<u8> = Vec::new();
+        let mut utf8: Vec<u8> = b"\xEF\xBB\xBF".to_vec();
+        let mut latin: Vec<u8> = Vec::new();
+        for i in 0..3000u32 {
+            sjis.extend_from_slice(b"\x82\xA0a\x82\x41bc\x81");
+            gb18030.extend_from_slice(b"\x81\x30\x81\x30xy\x81\x30z\xB0\xA1");
+            utf8.extend_from_slice("\u{3042}caf\u{e9}\u{1F600}".as_bytes());
+            utf8.push(if i % 7 == 0 { 0xE3 } else { b' ' });
+            latin.push((i % 256) as u8);
+        }
+        let cases: &[(&[u8], &'static encoding_rs::Encoding)] = &[
+            (&sjis, encoding_rs::SHIFT_JIS),
+            (&gb18030, encoding_rs::GB18030),
+            (&utf8, encoding_rs::UTF_8),
+            (&utf8, encoding_rs::WINDOWS_1252),
+            (&latin, encoding_rs::WINDOWS_1251),
+            (&latin, encoding_rs::ISO_2022_JP),
+        ];
+        for &(html, encoding) in cases {
+            let expected = encode_bytes(html, encoding.name());
+            for segment in [1, 5, 64, 1000] {
+                assert_eq!(
+                    parallel::par_decode(html, encoding, segment),
+                    expected,
+                    "{} segment {}",
+                    encoding.name(),
+                    segment
+                );
+            }
+        }
+        assert_eq!(par_encode_bytes(&sjis, "shift_jis"), encode_bytes(&sjis, "shift_jis"));
+        assert_eq!(par_auto_encode_bytes(&utf8), auto_encode_bytes(&utf8));
+        assert_eq!(par_encode_bytes(&sjis, "shiftjis"), "");
+    }
+
+    #[test]
+    fn test_decode_batch() {
+        let docs = stream_documents();
+        let refs: Vec<&[u8]> = docs.iter().map(|doc| &doc[..]).collect();
+        let expected: Vec<String> = refs.iter().map(|doc| auto_encode_bytes(doc)).collect();
+        assert_eq!(decode_batch(&refs), expected);
+        #[cfg(feature = "rayon")]
+        assert_eq!(par_decode_batch(&refs), expected);
+
+        let hinted: &[(&[u8], DecodeHint)] = &[
+            (b"\x82\xA0", DecodeHint::Label("shift_jis")),
+            (b"\x82\xA0", DecodeHint::Language("ja")),
+            (b"\xa1Hola!", DecodeHint::Auto),
+            (b"hello", DecodeHint::Label("shiftjis")),
+        ];
+        let expected = vec![
+            "\u{3042}".to_string(),
+            "\u{3042}".to_string(),
+            auto_encode_bytes(b"\xa1Hola!"),
+            String::new(),
+        ];
+        assert_eq!(decode_batch_with_hints(hinted), expected);
+        #[cfg(feature = "rayon")]
+        assert_eq!(par_decode_batch_with_hints(hinted), expected);
+    }
+
+    #[test]
+    fn test_try_encode_bytes() {
+        assert_eq!(try_encode_bytes(b"\x82\xA0", "shift_jis").unwrap(), "\u{3042}");
+        assert_eq!(try_encode_bytes(b"", "utf-8").unwrap(), "");
+        let err = try_encode_bytes(b"\x82\xA0", "shiftjis").unwrap_err();
+        assert_eq!(err, Error::UnknownLabel("shiftjis".to_string()));
+        assert_eq!(err.to_string(), "unknown encoding label: shiftjis");
+        assert_eq!(encode_bytes(b"\x82\xA0", "shiftjis"), "");
+    }
+
+    #[test]
+    fn test_try_encode_bytes_strict() {
+        assert_eq!(
+            try_encode_bytes_strict(b"\x82\xA0", "shift_jis").unwrap(),
+            "\u{3042}"
+        );
+        assert_eq!(
+            try_encode_bytes_strict(b"ab\xFFcd", "utf-8"),
+            Err(Error::Malformed {
+                encoding: encoding_rs::UTF_8,
+                position: 2
+            })
+        );
+        assert_eq!(
+            try_encode_bytes_strict(b"a\x82\xA0\x82", "shift_jis"),
+            Err(Error::Malformed {
+                encoding: encoding_rs::SHIFT_JIS,
+                position: 3
+            })
+        );
+        assert_eq!(try_encode_bytes(b"ab\xFFcd", "utf-8").unwrap(), "ab\u{FFFD}cd");
+    }
+
+    #[test]
+    fn test_auto_encoder_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<AutoEncoder>();
+        assert_send_sync::<Decoder>();
+    }
+
+    #[test]
+    fn test_auto_encoder_defaults_match_auto_encode_bytes() {
+        let encoder = AutoEncoder::default();
+        for doc in stream_documents() {
             assert_eq!(
-                is_binary_file(case),
-                detect::is_binary_file_phf(case),
-                "Mismatch for {:?}",
-                case
+                encoder.decode(&doc).unwrap_or_default(),
+                auto_encode_bytes(&doc)
             );
         }
+        assert_eq!(
+            encoder.decode(br#"<meta charset="x-unknown">hello"#),
+            Err(Error::UnknownLabel("x-unknown".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_auto_encoder_options() {
+        let mut html = b"<html>".to_vec();
+        html.extend_from_slice(&[b' '; 1100]);
+        html.extend_from_slice(br#"<meta charset="koi8-r">"#);
+        html.extend_from_slice(b"caf\xE9 cr\xE8me");
+
+        let declared = AutoEncoder::builder().prescan_window(2048).build();
+        assert_eq!(declared.detect(&html), Ok(encoding_rs::KOI8_R));
+        assert_eq!(declared.decode(&html).unwrap(), encode_bytes(&html, "koi8-r"));
+        assert_ne!(
+            AutoEncoder::default().detect(&html),
+            Ok(encoding_rs::KOI8_R)
+        );
+
+        let untrusted = AutoEncoder::builder()
+            .prescan_window(2048)
+            .trust_declared_charset(false)
+            .locale("ru-ru")
+            .build();
+        assert_eq!(untrusted.detect(&html), Ok(encoding_rs::WINDOWS_1251));
+
+        let fallback = AutoEncoder::builder()
+            .fallback(encoding_rs::WINDOWS_1252)
+            .build();
+        assert_eq!(
+            fallback.detect(br#"<meta charset="x-unknown">"#),
+            Ok(encoding_rs::WINDOWS_1252)
+        );
+
+        let bytes = b"\xC8\xE0\xF0\xE0 \xE8 \xEC\xE8\xF0";
+        assert_eq!(
+            AutoEncoder::builder().tld("example.RU").build().detect(bytes),
+            Ok(guess_encoding(bytes, Some(b"ru")).0)
+        );
+
+        let strict = AutoEncoder::builder().strict(true).build();
+        assert_eq!(
+            strict.decode_with_label(b"ok\xFF", "utf-8"),
+            Err(Error::Malformed {
+                encoding: encoding_rs::UTF_8,
+                position: 2
+            })
+        );
+
+        let limited = AutoEncoder::builder().max_output_len(10).build();
+        let mut output = String::from("keep");
+        assert_eq!(
+            limited.decode_with_label_into(&[b'a'; 20000], "utf-8", &mut output),
+            Err(Error::OutputLimit { limit: 10 })
+        );
+        assert_eq!(output, "keep");
+        assert_eq!(limited.decode(b"short").unwrap(), "short");
+    }
+
+    #[test]
+    fn test_decode_with_offsets() {
+        let mut sjis = Vec::new();
+        let mut utf8 = b"\xEF\xBB\xBF".to_vec();
+        let mut latin = Vec::new();
+        for i in 0..40u8 {
+            sjis.extend_from_slice(b"\x82\xA0ab\x82\xA2 cd\x81 \x82");
+            utf8.extend_from_slice("\u{3042}x caf\u{e9} \u{1F600}".as_bytes());
+            utf8.push(if i % 5 == 0 { 0xE3 } else { b'.' });
+            latin.extend_from_slice(&[i, 0xE9, b' ', 0xF0 - i]);
+        }
+        let mut utf16le = b"\xFF\xFE".to_vec();
+        let mut utf16be = Vec::new();
+        for unit in "a\u{3042} \u{1F600}b".encode_utf16().chain([0xD800, 0x63]).cycle().take(200) {
+            utf16le.extend_from_slice(&unit.to_le_bytes());
+            utf16be.extend_from_slice(&unit.to_be_bytes());
+        }
+        let cases: &[(&[u8], &'static encoding_rs::Encoding)] = &[
+            (&sjis, encoding_rs::SHIFT_JIS),
+            (&utf8, encoding_rs::UTF_8),
+            (&latin, encoding_rs::WINDOWS_1251),
+            (b"\x1B$B$\"$$\x1B(Babc", encoding_rs::ISO_2022_JP),
+            (&utf16le, encoding_rs::UTF_16LE),
+            (&utf16be, encoding_rs::UTF_16BE),
+        ];
+        for &(html, encoding) in cases {
+            for interval in [1, 3, 16, 1000] {
+                let (text, map) = decode_with_offsets(html, encoding, interval);
+                assert_eq!(text, encode_bytes(html, encoding.name()));
+                assert_eq!(map.samples().last(), Some(&(html.len(), text.len())));
+                for &(original, decoded) in map.samples() {
+                    assert_eq!(map.to_decoded(html, original), decoded);
+                    assert_eq!(map.to_original(html, decoded), original);
+                }
+                for offset in 0..html.len() {
+                    let mut prefix = String::new();
+                    decode_chunk(&mut encoding.new_decoder(), &html[..offset], false, &mut prefix);
+                    assert_eq!(map.to_decoded(html, offset), prefix.len());
+                    let original = map.to_original(html, prefix.len());
+                    assert_eq!(map.to_decoded(html, original), prefix.len());
+                }
+            }
+        }
+
+        let (text, map) = AutoEncoder::default()
+            .decode_with_offsets(b"\x82\xA0\x82\xA2", 1)
+            .unwrap();
+        assert_eq!(text, "\u{3042}\u{3044}");
+        assert_eq!(map.to_original(b"\x82\xA0\x82\xA2", 3), 2);
+        assert_eq!(map.to_original(b"\x82\xA0\x82\xA2", 4), 2);
+
+        // the space comes out with the replacement for the lone lead byte before it
+        let (text, map) = decode_with_offsets(b"a\x81 b", encoding_rs::SHIFT_JIS, 1000);
+        assert_eq!(text, "a\u{FFFD} b");
+        assert_eq!(map.to_original(b"a\x81 b", 1), 1);
+        assert_eq!(map.to_original(b"a\x81 b", 4), 2);
+        assert_eq!(map.to_original(b"a\x81 b", 5), 3);
+
+        let (_, map) = decode_with_offsets(&utf16le, encoding_rs::UTF_16LE, 16);
+        assert!(map.samples().len() > 2);
+        assert!(map.samples().iter().all(|&(original, _)| original % 2 == 0));
+    }
+
+    #[cfg(feature = "normalization")]
+    #[test]
+    fn test_normalization() {
+        // windows-1258 keeps the dot below as a separate combining character.
+        let html = b"Vi\xEA\xF2t \xE0";
+        assert_eq!(encode_bytes(html, "windows-1258"), "Vi\u{ea}\u{323}t \u{e0}");
+        assert_eq!(
+            encode_bytes_normalized(html, "windows-1258", Normalization::Nfc),
+            "Vi\u{1ec7}t \u{e0}"
+        );
+        assert_eq!(
+            auto_encode_bytes_normalized("\u{ff21}e\u{301}".as_bytes(), Normalization::Nfkc),
+            "A\u{e9}"
+        );
+        assert_eq!(
+            auto_encode_bytes_normalized("\u{ff21}e\u{301}".as_bytes(), Normalization::Nfc),
+            "\u{ff21}\u{e9}"
+        );
+        let mut output = String::from("e\u{301}:");
+        AutoEncoder::builder()
+            .normalization(Normalization::Nfc)
+            .build()
+            .decode_with_label_into(b"e\xCC\x81", "utf-8", &mut output)
+            .unwrap();
+        assert_eq!(output, "e\u{301}:\u{e9}");
+    }
+
+    #[cfg(feature = "normalization")]
+    #[test]
+    fn test_output_limit_holds_after_normalization() {
+        let builder = AutoEncoder::builder()
+            .normalization(Normalization::Nfkc)
+            .max_output_chars(3);
+        let html = "\u{FDFA}".as_bytes();
+        assert_eq!(
+            builder.clone().build().decode(html),
+            Err(Error::OutputLimit { limit: 3 })
+        );
+        let (output, report) = builder
+            .limit_policy(LimitPolicy::Truncate)
+            .build()
+            .decode_with_report(html)
+            .unwrap();
+        assert_eq!(output, "\u{635}\u{644}\u{649}");
+        assert!(report.truncated);
+    }
+
+    #[test]
+    fn test_rewrite_declared_charset() {
+        let encoder = AutoEncoder::builder().rewrite_declared_charset(true).build();
+
+        let mut html = br#"<html><head><meta charset="shift_jis"><meta http-equiv="Content-Type" content="text/html; charset=Shift_JIS"></head><body>"#.to_vec();
+        html.extend_from_slice(b"\x82\xA0</body></html>");
+        assert_eq!(
+            encoder.decode(&html).unwrap(),
+            "<html><head><meta charset=\"utf-8\"><meta http-equiv=\"Content-Type\" content=\"text/html; charset=utf-8\"></head><body>\u{3042}</body></html>"
+        );
+
+        let xml = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><p>caf\xE9</p>";
+        assert_eq!(
+            encoder.decode_with_label(xml, "iso-8859-1").unwrap(),
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?><p>caf\u{e9}</p>"
+        );
+
+        let mut output = String::from("<meta charset=\"gbk\">");
+        encoder
+            .decode_with_label_into(b"\xEF\xBB\xBF\xEF\xBB\xBF<meta charset='gbk'>", "utf-8", &mut output)
+            .unwrap();
+        assert_eq!(output, "<meta charset=\"gbk\"><meta charset='utf-8'>");
+
+        assert_eq!(
+            detect::detect_charset_declarations(b"<meta charset=\"koi8-r\"><meta charset=\"x\">"),
+            vec![15..21, 38..39]
+        );
+    }
+
+    #[test]
+    fn test_rewrite_declared_charset_follows_prescan_window() {
+        let encoder = AutoEncoder::builder().rewrite_declared_charset(true).build();
+        // 800 input bytes decode to 1200 output bytes before the declaration
+        let mut html = b"\x82\xA0".repeat(400);
+        html.extend_from_slice(br#"<meta charset="shift_jis">"#);
+        assert_eq!(
+            encoder.decode(&html).unwrap(),
+            format!("{}<meta charset=\"utf-8\">", "\u{3042}".repeat(400))
+        );
+
+        let mut html = b" ".repeat(1106);
+        html.extend_from_slice(b"<meta charset=\"koi8-r\">\xf0\xd2\xc9\xd7\xc5\xd4");
+        let wide = AutoEncoder::builder()
+            .prescan_window(2048)
+            .rewrite_declared_charset(true)
+            .build();
+        let output = wide.decode(&html).unwrap();
+        assert!(output.ends_with("<meta charset=\"utf-8\">\u{41f}\u{440}\u{438}\u{432}\u{435}\u{442}"));
+
+        let mut decoder = StreamDecoder::with_config(wide);
+        let mut streamed = String::new();
+        for chunk in html.chunks(100) {
+            decoder.push(chunk, &mut streamed);
+        }
+        decoder.finish(&mut streamed);
+        assert_eq!(streamed, output);
+    }
+
+    #[test]
+    fn test_charset_declarations_stay_inside_meta() {
+        let encoder = AutoEncoder::builder().rewrite_declared_charset(true).build();
+        for html in [
+            &br#"<meta name="viewport" content="x"><p>Set charset="latin1" in your editor</p>"#[..],
+            br#"<meta name="x"><p>http-equiv="Content-Type" content="text/html; charset=koi8-r"</p>"#,
+        ] {
+            assert_eq!(detect_encoding(html), None);
+            assert!(detect::detect_charset_declarations(html).is_empty());
+            assert_eq!(encoder.decode(html).unwrap().as_bytes(), html);
+        }
+        let html = br#"<meta http-equiv="Content-Type" content="text/html; charset=koi8-r">"#;
+        assert_eq!(detect_encoding(html).as_deref(), Some("koi8-r"));
+    }
+
+    #[test]
+    fn test_detect_unquoted_meta_charset() {
+        let html = b"<meta charset=shift_jis><p>\x82\xA0</p>";
+        assert_eq!(detect_encoding(html).as_deref(), Some("shift_jis"));
+        assert_eq!(auto_encode_bytes(html), "<meta charset=shift_jis><p>\u{3042}</p>");
+        assert_eq!(detect_encoding(b"<meta charset=koi8-r/>").as_deref(), Some("koi8-r"));
+        assert_eq!(detect_encoding(b"<meta charset='koi8-r'>").as_deref(), Some("koi8-r"));
+
+        let encoder = AutoEncoder::builder().rewrite_declared_charset(true).build();
+        assert_eq!(encoder.decode(html).unwrap(), "<meta charset=utf-8><p>\u{3042}</p>");
+
+        // an unquoted label only counts inside the meta element
+        assert_eq!(detect_encoding(b"<meta name=x>charset=koi8-r"), None);
+        assert_eq!(detect_encoding(b"<meta charset=>"), None);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_decode_to_bytes() {
+        let html = ::bytes::Bytes::from("<html><body>\u{3042}</body></html>");
+        let text = auto_encode_to_bytes(html.clone());
+        assert_eq!(text.as_ptr(), html.as_ptr());
+        assert_eq!(text, html);
+
+        let with_bom = ::bytes::Bytes::from_static(b"\xEF\xBB\xBF<p>hi</p>");
+        let text = encode_to_bytes(with_bom.clone(), "windows-1252");
+        assert_eq!(&text[..], b"<p>hi</p>");
+        assert_eq!(text.as_ptr(), with_bom[3..].as_ptr());
+
+        let ascii = ::bytes::BytesMut::from(&b"<meta charset=\"shift_jis\"><p>hi</p>"[..]);
+        let text = auto_encode_to_bytes(ascii.clone());
+        assert_eq!(&text[..], &ascii[..]);
+
+        let mut html = b"<meta charset=\"shift_jis\">".to_vec();
+        html.extend_from_slice(b"\x82\xA0");
+        let text = auto_encode_to_bytes(html.clone());
+        assert_eq!(&text[..], auto_encode_bytes(&html).as_bytes());
+        assert_eq!(encode_to_bytes(html, "unknown-label"), ::bytes::Bytes::new());
+
+        let limited = AutoEncoder::builder().max_output_len(4).build();
+        assert!(matches!(
+            limited.decode_bytes(::bytes::Bytes::from_static(b"hello")),
+            Err(Error::OutputLimit { limit: 4 })
+        ));
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        let text = "a\u{e9}\u{3042}b".repeat(4000);
+        let html = text.as_bytes();
+
+        let (output, report) = AutoEncoder::builder()
+            .max_output_len(9)
+            .limit_policy(LimitPolicy::Truncate)
+            .build()
+            .decode_with_report(html)
+            .unwrap();
+        assert_eq!(output, "a\u{e9}\u{3042}ba");
+        assert!(report.truncated);
+        assert_eq!(report.encoding, encoding_rs::UTF_8);
+
+        let encoder = AutoEncoder::builder()
+            .max_output_chars(20_001)
+            .limit_policy(LimitPolicy::Truncate)
+            .build();
+        let (output, report) = encoder.decode_with_report(html).unwrap();
+        assert_eq!(output.chars().count(), 16_000);
+        assert!(!report.truncated);
+        let (output, report) = encoder.decode_with_report(&html.repeat(2)).unwrap();
+        assert_eq!(output.chars().count(), 20_001);
+        assert!(text.repeat(2).starts_with(&output));
+        assert!(report.truncated);
+
+        let mut output = String::from("prefix");
+        let report = AutoEncoder::builder()
+            .max_output_chars(3)
+            .limit_policy(LimitPolicy::Truncate)
+            .build()
+            .decode_with_report_into(b"\x82\xA0\x82\xA2\x82\xA4\x82\xA6", &mut output)
+            .unwrap();
+        assert_eq!(output, "prefix\u{3042}\u{3044}\u{3046}");
+        assert!(report.truncated);
+
+        assert!(matches!(
+            AutoEncoder::builder().max_output_chars(3).build().decode(b"abcd"),
+            Err(Error::OutputLimit { limit: 3 })
+        ));
+    }
+
+    #[cfg(feature = "graphemes")]
+    #[test]
+    fn test_truncate_output_graphemes() {
+        let html = "ae\u{301}e\u{301}".as_bytes();
+        let encoder = AutoEncoder::builder()
+            .max_output_chars(4)
+            .limit_policy(LimitPolicy::TruncateGraphemes)
+            .build();
+        let (output, report) = encoder.decode_with_report(html).unwrap();
+        assert_eq!(output, "ae\u{301}");
+        assert!(report.truncated);
+        let (output, report) = encoder.decode_with_report("ae\u{301}e".as_bytes()).unwrap();
+        assert_eq!(output, "ae\u{301}e");
+        assert!(!report.truncated);
+    }
+
+    #[test]
+    fn test_lossless_round_trip() {
+        let mut state = 0x2545_f491_u32;
+        let mut noise = Vec::new();
+        for _ in 0..4096 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            noise.push(state as u8);
+        }
+        let mut html = "<p>\u{3042}\u{e9}\u{10ff41}</p>".as_bytes().to_vec();
+        html.extend_from_slice(&noise);
+        html.extend_from_slice(b"\x82\xA0\xED\x40\xFA\x5C\x81");
+
+        for label in [
+            "utf-8",
+            "windows-1252",
+            "shift_jis",
+            "euc-jp",
+            "euc-kr",
+            "big5",
+            "gbk",
+            "gb18030",
+            "x-user-defined",
+        ] {
+            let text = decode_lossless(&html, label).unwrap();
+            assert_eq!(encode_lossless(&text, label).unwrap(), html, "{}", label);
+        }
+
+        let (text, encoding) = auto_decode_lossless(b"\xEF\xBB\xBFok\xFF").unwrap();
+        assert_eq!(encoding, encoding_rs::UTF_8);
+        assert_eq!(text, "\u{feff}ok\u{10ffff}");
+
+        // characters of the escape range are escaped byte by byte
+        let text = decode_lossless("a\u{10ff41}".as_bytes(), "utf-8").unwrap();
+        assert_eq!(text, "a\u{10fff4}\u{10ff8f}\u{10ffbd}\u{10ff81}");
+
+        // both NEC and IBM codes decode to U+7E8A but only one encodes back
+        let text = decode_lossless(b"\xED\x40\xFA\x5C", "shift_jis").unwrap();
+        assert_eq!(text.chars().filter(|&c| c == '\u{7e8a}').count(), 1);
+
+        let lossless = Lossless::new('\u{E000}').unwrap();
+        let text = lossless.decode(b"a\x80", encoding_rs::UTF_8).unwrap();
+        assert_eq!(text, "a\u{e080}");
+        assert_eq!(lossless.encode(&text, encoding_rs::UTF_8).unwrap(), b"a\x80");
+        assert!(Lossless::new('\u{D780}').is_none());
+        assert!(Lossless::new('\u{10FF01}').is_none());
+
+        assert_eq!(
+            decode_lossless(b"a", "utf-16le"),
+            Err(Error::UnsupportedEncoding(encoding_rs::UTF_16LE))
+        );
+        assert!(matches!(
+            encode_lossless("\u{3042}", "windows-1252"),
+            Err(Error::Unmappable {
+                character: '\u{3042}',
+                position: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn test_stream_decoder_with_config_matches_auto_encoder() {
+        let late_meta = [
+            " ".repeat(1100).into_bytes(),
+            b"<meta charset=\"koi8-r\">\xf0\xd2\xc9\xd7\xc5\xd4".to_vec(),
+        ]
+        .concat();
+        let configs = [
+            AutoEncoder::builder().prescan_window(2048).build(),
+            AutoEncoder::builder().locale("ja-jp").build(),
+            AutoEncoder::builder().fallback(encoding_rs::WINDOWS_1251).build(),
+            AutoEncoder::builder().strict(true).build(),
+            AutoEncoder::builder()
+                .max_output_chars(5)
+                .limit_policy(LimitPolicy::Truncate)
+                .build(),
+            AutoEncoder::builder().max_output_len(5).build(),
+            AutoEncoder::builder().rewrite_declared_charset(true).build(),
+        ];
+        let mut docs = stream_documents();
+        docs.push(late_meta);
+        docs.push(b"\x82\xA0\x82\xA2".to_vec());
+        docs.push(b"<meta charset=\"utf-8\">bad \xff".to_vec());
+
+        for config in &configs {
+            for doc in &docs {
+                let expected = config.decode(doc);
+                for size in [1, 7, 1000, 4096] {
+                    let mut decoder = StreamDecoder::with_config(config.clone());
+                    let mut output = String::new();
+                    for chunk in doc.chunks(size) {
+                        decoder.push(chunk, &mut output);
+                    }
+                    decoder.finish(&mut output);
+                    match &expected {
+                        Ok(expected) => {
+                            assert_eq!(&output, expected, "{:?} chunk size {}", config, size);
+                            assert_eq!(decoder.error(), None);
+                        }
+                        Err(error) => assert_eq!(decoder.error(), Some(error)),
+                    }
+                }
+            }
+        }
+    }
+
+    fn stream_documents() -> Vec<Vec<u8>> {
+        let mut declared = br#"<html><head><meta charset="shift_jis"></head><body>"#.to_vec();
+        let mut sniffed = b"<html><body>".to_vec();
+        for _ in 0..600 {
+            declared.extend_from_slice(b"\x82\xA0\x82\xA2");
+            sniffed.extend_from_slice(b"\xCF\xF0\xE8\xE2\xE5\xF2 ");
+        }
+        let mut bom = b"\xEF\xBB\xBF".to_vec();
+        bom.extend_from_slice("caf\u{e9} ".repeat(300).as_bytes());
+        vec![
+            declared,
+            sniffed,
+            bom,
+            br#"<meta charset="x-unknown">hello"#.to_vec(),
+            b"\xa1Hola!".to_vec(),
+            Vec::new(),
+        ]
+    }
+
+    #[test]
+    fn test_stream_decoder_matches_auto_encode_bytes() {
+        for doc in stream_documents() {
+            for size in [1, 7, 1000, 4096] {
+                let mut decoder = StreamDecoder::new();
+                let mut output = String::new();
+                for chunk in doc.chunks(size) {
+                    decoder.push(chunk, &mut output);
+                }
+                decoder.finish(&mut output);
+                assert_eq!(output, auto_encode_bytes(&doc), "chunk size {}", size);
+            }
+        }
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn test_decode_stream_matches_auto_encode_bytes() {
+        use futures_util::StreamExt;
+
+        for doc in stream_documents() {
+            let chunks: Vec<Vec<u8>> = doc.chunks(100).map(|c| c.to_vec()).collect();
+            let stream = DecodeStream::new(futures_util::stream::iter(chunks));
+            let output: Vec<String> = futures_executor::block_on(stream.collect());
+            assert!(output.iter().all(|chunk| !chunk.is_empty()));
+            assert_eq!(output.concat(), auto_encode_bytes(&doc));
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_decode_reader_matches_auto_encode_bytes() {
+        use tokio::io::AsyncReadExt;
+
+        for doc in stream_documents() {
+            let mut reader = DecodeReader::new(&doc[..]);
+            let mut output = String::new();
+            reader.read_to_string(&mut output).await.unwrap();
+            assert_eq!(output, auto_encode_bytes(&doc));
+        }
     }
 
     #[ignore]
@@ -422,7 +1706,7 @@ mod tests {
         }
         .into_string();
         assert!(
-            detect_encoding(&markup.as_bytes())
+            detect_encoding(markup.as_bytes())
                 .unwrap_or_default()
                 .to_lowercase()
                 == "utf-8"
@@ -0,0 +1,109 @@
+/// The maximum size of the decoded output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputLimit {
+    /// The length in UTF-8 bytes.
+    Bytes(usize),
+    /// The number of characters.
+    Chars(usize),
+}
+
+impl OutputLimit {
+    /// The configured amount of bytes or characters.
+    pub fn value(self) -> usize {
+        match self {
+            OutputLimit::Bytes(limit) | OutputLimit::Chars(limit) => limit,
+        }
+    }
+
+    /// The same kind of limit with another value.
+    pub(crate) fn with_value(self, value: usize) -> Self {
+        match self {
+            OutputLimit::Bytes(_) => OutputLimit::Bytes(value),
+            OutputLimit::Chars(_) => OutputLimit::Chars(value),
+        }
+    }
+
+    /// The size of the text in the unit of the limit.
+    pub(crate) fn measure(self, text: &str) -> usize {
+        match self {
+            OutputLimit::Bytes(_) => text.len(),
+            OutputLimit::Chars(_) => text.chars().count(),
+        }
+    }
+}
+
+/// What happens once the decoded output exceeds the [`OutputLimit`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LimitPolicy {
+    /// Stop decoding with [`crate::Error::OutputLimit`].
+    #[default]
+    Error,
+    /// Stop decoding and cut the output at the last character boundary within the limit.
+    Truncate,
+    /// Stop decoding and cut the output at the last grapheme cluster boundary within the limit.
+    #[cfg(feature = "graphemes")]
+    TruncateGraphemes,
+}
+
+/// Tracks decoded output appended after `start` against a limit as it grows.
+pub(crate) struct LimitCheck {
+    limit: OutputLimit,
+    start: usize,
+    checked: usize,
+    chars: usize,
+}
+
+impl LimitCheck {
+    pub(crate) fn new(limit: OutputLimit, start: usize) -> Self {
+        Self {
+            limit,
+            start,
+            checked: start,
+            chars: 0,
+        }
+    }
+
+    /// The offset to cut `output` at to fit the limit, if the output grew past it.
+    pub(crate) fn exceeded(&mut self, output: &str) -> Option<usize> {
+        match self.limit {
+            OutputLimit::Bytes(limit) => {
+                let mut end = self.start + limit;
+                if output.len() <= end {
+                    return None;
+                }
+                while !output.is_char_boundary(end) {
+                    end -= 1;
+                }
+                Some(end)
+            }
+            OutputLimit::Chars(limit) => {
+                for (i, _) in output[self.checked..].char_indices() {
+                    if self.chars == limit {
+                        return Some(self.checked + i);
+                    }
+                    self.chars += 1;
+                }
+                self.checked = output.len();
+                None
+            }
+        }
+    }
+}
+
+/// Move the character boundary `cut` back to the grapheme cluster boundary at or before it,
+/// looking at the text of `output` after `start` only.
+#[cfg(feature = "graphemes")]
+pub(crate) fn grapheme_floor(output: &str, start: usize, cut: usize) -> usize {
+    use unicode_segmentation::GraphemeCursor;
+
+    let tail = &output[start..];
+    let mut cursor = GraphemeCursor::new(cut - start, tail.len(), true);
+    match cursor.is_boundary(tail, 0) {
+        Ok(true) => cut,
+        _ => match cursor.prev_boundary(tail, 0) {
+            Ok(Some(boundary)) => start + boundary,
+            _ => start,
+        },
+    }
+}
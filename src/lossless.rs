@@ -0,0 +1,269 @@
+use crate::encode::{encode_chunk, UnmappablePolicy};
+use crate::error::Error;
+use encoding_rs::{DecoderResult, Encoding};
+
+/// The first character of the default escape range, byte `b` is escaped as U+10FF00 + `b`.
+pub const DEFAULT_ESCAPE_BASE: char = '\u{10FF00}';
+
+/// Decoding that keeps undecodable bytes as private-use characters, so the text encodes back to the exact original bytes.
+///
+/// Bytes that are malformed, decode to a character of the escape range, or decode to text that would encode
+/// differently are each escaped as the character at the escape base plus the byte value. Encoding turns any
+/// character of the escape range back into its byte. Stateful encodings like ISO-2022-JP and UTF-16 are not supported.
+///
+/// ```rust
+/// use auto_encoder::Lossless;
+///
+/// let lossless = Lossless::default();
+/// let html = b"caf\xE9 \x82\xA0\x82";
+/// let text = lossless.decode(html, auto_encoder::encoding_rs::SHIFT_JIS).unwrap();
+/// assert_eq!(text, "caf\u{10FFE9} \u{3042}\u{10FF82}");
+/// let bytes = lossless.encode(&text, auto_encoder::encoding_rs::SHIFT_JIS).unwrap();
+/// assert_eq!(bytes, html);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lossless {
+    base: u32,
+}
+
+impl Default for Lossless {
+    fn default() -> Self {
+        Self {
+            base: DEFAULT_ESCAPE_BASE as u32,
+        }
+    }
+}
+
+impl Lossless {
+    /// Escape bytes from the character `base` on. Returns `None` when the 256 characters from `base` are not all valid,
+    /// like when the range includes surrogates or goes past U+10FFFF.
+    pub fn new(base: char) -> Option<Self> {
+        let base = base as u32;
+        (0..=0xFF)
+            .all(|byte| char::from_u32(base + byte).is_some())
+            .then_some(Self { base })
+    }
+
+    /// The character byte 0 is escaped as.
+    pub fn base(&self) -> char {
+        char::from_u32(self.base).unwrap_or(DEFAULT_ESCAPE_BASE)
+    }
+
+    /// Decode the content with the encoding, escaping the bytes that would not round-trip.
+    pub fn decode(&self, html: &[u8], encoding: &'static Encoding) -> Result<String, Error> {
+        let mut output = String::new();
+        self.decode_into(html, encoding, &mut output)?;
+        Ok(output)
+    }
+
+    /// Decode the content with the encoding, appending the text to `output`. Nothing is appended on error.
+    pub fn decode_into(
+        &self,
+        html: &[u8],
+        encoding: &'static Encoding,
+        output: &mut String,
+    ) -> Result<(), Error> {
+        if !is_supported(encoding) {
+            return Err(Error::UnsupportedEncoding(encoding));
+        }
+        let mut pos = 0;
+
+        // decode up to each malformed sequence, restarting after it from a fresh decoder
+        loop {
+            let remaining = &html[pos..];
+            let start = output.len();
+            let mut decoder = encoding.new_decoder_without_bom_handling();
+            let (result, read) = decode_run(&mut decoder, remaining, true, output);
+            let (run_len, resume) = match result {
+                DecoderResult::Malformed(bad, extra) => {
+                    (read - bad as usize - extra as usize, read - extra as usize)
+                }
+                _ => (read, read),
+            };
+
+            let run = &remaining[..run_len];
+            if self.collides(&output[start..])
+                || (encoding != encoding_rs::UTF_8 && !encodes_to(encoding, &output[start..], run))
+            {
+                output.truncate(start);
+                self.decode_units(encoding, run, output);
+            }
+            for &byte in &remaining[run_len..resume] {
+                self.escape(byte, output);
+            }
+
+            pos += resume;
+            if run_len == read {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Encode the text to the encoding, turning escaped characters back into their bytes.
+    pub fn encode(&self, text: &str, encoding: &'static Encoding) -> Result<Vec<u8>, Error> {
+        let mut output = Vec::new();
+        self.encode_into(text, encoding, &mut output)?;
+        Ok(output)
+    }
+
+    /// Encode the text to the encoding, appending the bytes to `output`.
+    /// Characters the encoding cannot represent fail with [`Error::Unmappable`].
+    pub fn encode_into(
+        &self,
+        text: &str,
+        encoding: &'static Encoding,
+        output: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        if !is_supported(encoding) {
+            return Err(Error::UnsupportedEncoding(encoding));
+        }
+        let mut encoder = encoding.new_encoder();
+        let mut run_start = 0;
+
+        for (i, character) in text.char_indices() {
+            if let Some(byte) = self.unescape(character) {
+                let run = &text[run_start..i];
+                encode_chunk(
+                    &mut encoder,
+                    run,
+                    run_start,
+                    false,
+                    UnmappablePolicy::Error,
+                    output,
+                )?;
+                output.push(byte);
+                run_start = i + character.len_utf8();
+            }
+        }
+
+        let run = &text[run_start..];
+        encode_chunk(
+            &mut encoder,
+            run,
+            run_start,
+            true,
+            UnmappablePolicy::Error,
+            output,
+        )
+    }
+
+    /// Decode a run without malformed bytes one character at a time, escaping the characters that do not round-trip.
+    fn decode_units(&self, encoding: &'static Encoding, run: &[u8], output: &mut String) {
+        let mut decoder = encoding.new_decoder_without_bom_handling();
+        let mut unit = String::new();
+        let mut unit_start = 0;
+
+        for (i, &byte) in run.iter().enumerate() {
+            let last = i + 1 == run.len();
+            let (result, _) = decode_run(&mut decoder, &[byte], last, &mut unit);
+            let malformed = matches!(result, DecoderResult::Malformed(..));
+            if !malformed && unit.is_empty() && !last {
+                continue;
+            }
+
+            let bytes = &run[unit_start..=i];
+            if malformed || self.collides(&unit) || !encodes_to(encoding, &unit, bytes) {
+                for &byte in bytes {
+                    self.escape(byte, output);
+                }
+            } else {
+                output.push_str(&unit);
+            }
+            if malformed {
+                decoder = encoding.new_decoder_without_bom_handling();
+            }
+            unit.clear();
+            unit_start = i + 1;
+        }
+    }
+
+    fn escape(&self, byte: u8, output: &mut String) {
+        if let Some(character) = char::from_u32(self.base + byte as u32) {
+            output.push(character);
+        }
+    }
+
+    fn unescape(&self, character: char) -> Option<u8> {
+        let byte = (character as u32).checked_sub(self.base)?;
+        u8::try_from(byte).ok()
+    }
+
+    fn collides(&self, text: &str) -> bool {
+        text.chars()
+            .any(|character| self.unescape(character).is_some())
+    }
+}
+
+/// Decode the content with the encoding, escaping the bytes that would not round-trip with [`DEFAULT_ESCAPE_BASE`].
+/// Pass in a proper encoding label like SHIFT_JIS.
+pub fn decode_lossless(html: &[u8], label: &str) -> Result<String, Error> {
+    let encoding = Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| Error::UnknownLabel(label.to_string()))?;
+    Lossless::default().decode(html, encoding)
+}
+
+/// Get the content with proper encoding, escaping the bytes that would not round-trip with [`DEFAULT_ESCAPE_BASE`].
+/// Returns the encoding to pass to [`Lossless::encode`] along with the text.
+pub fn auto_decode_lossless(html: &[u8]) -> Result<(String, &'static Encoding), Error> {
+    let encoding = if html.is_empty() {
+        encoding_rs::UTF_8
+    } else {
+        crate::AutoEncoder::default().detect(html)?
+    };
+    Ok((Lossless::default().decode(html, encoding)?, encoding))
+}
+
+/// Encode text from [`decode_lossless`] back to the original bytes. Pass in the same encoding label.
+pub fn encode_lossless(text: &str, label: &str) -> Result<Vec<u8>, Error> {
+    let encoding = Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| Error::UnknownLabel(label.to_string()))?;
+    Lossless::default().encode(text, encoding)
+}
+
+/// Whether every character of the encoding maps to bytes independently of the characters around it.
+fn is_supported(encoding: &'static Encoding) -> bool {
+    encoding != encoding_rs::UTF_16LE
+        && encoding != encoding_rs::UTF_16BE
+        && encoding != encoding_rs::ISO_2022_JP
+        && encoding != encoding_rs::REPLACEMENT
+}
+
+/// Decode the input up to the end or the first malformed sequence, returning the result and the bytes read.
+fn decode_run(
+    decoder: &mut encoding_rs::Decoder,
+    input: &[u8],
+    last: bool,
+    output: &mut String,
+) -> (DecoderResult, usize) {
+    let mut total_read = 0usize;
+
+    loop {
+        let remaining = &input[total_read..];
+        output.reserve(
+            decoder
+                .max_utf8_buffer_length_without_replacement(remaining.len())
+                .unwrap_or(remaining.len()),
+        );
+        let (result, read) = decoder.decode_to_string_without_replacement(remaining, output, last);
+        total_read += read;
+        if result != DecoderResult::OutputFull {
+            return (result, total_read);
+        }
+    }
+}
+
+/// Whether encoding the text gives exactly the bytes.
+fn encodes_to(encoding: &'static Encoding, text: &str, bytes: &[u8]) -> bool {
+    let mut encoded = Vec::with_capacity(bytes.len());
+    let mut encoder = encoding.new_encoder();
+    encode_chunk(
+        &mut encoder,
+        text,
+        0,
+        true,
+        UnmappablePolicy::Error,
+        &mut encoded,
+    )
+    .is_ok()
+        && encoded == bytes
+}
@@ -1,6 +1,7 @@
+use crate::file_type::FileType;
 use phf::phf_map;
 
-/// Define a map of file types to their numbers
+/// Define a map of file types to their numbers. Plain prefixes only, see [`crate::detect_file_type`] for detection.
 pub static ASSET_NUMBERS: phf::Map<&'static str, &'static [u8]> = phf_map! {
     "jpeg" => &[0xFF, 0xD8, 0xFF],
     "pdf" => b"%PDF",
@@ -26,8 +27,9 @@ pub static ASSET_NUMBERS: phf::Map<&'static str, &'static [u8]> = phf_map! {
     "bzip" => &[0x42, 0x5A, 0x68],
     "bzip2" => &[0x42, 0x5A, 0x68],          // BZip2, "BZh"
     "java_class" => &[0xCA, 0xFE, 0xBA, 0xBE],
-    "lha" => &[0x4C],  // Placeholder or check specific variant
     "elf" => &[0x7F, 0x45, 0x4C, 0x46], // 0x7F followed by 'ELF'
+    "rar" => &[0x52, 0x61, 0x72, 0x21, 0x1A, 0x07], // "Rar!" followed by 0x1A 0x07
+    "7z" => &[0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C],
 };
 
 /// Map of first byte to the corresponding magic number key(s)
@@ -48,12 +50,220 @@ pub static FIRST_BYTE_MAP: phf::Map<u8, &'static [&'static str]> = phf_map! {
     0x1Fu8 => &["gzip"],
     0x25u8 => &["pdf"],
     0x38u8 => &["gif"],
-    0x5Au8 => &["7z"],
+    0x37u8 => &["7z"],
     0xCAu8 => &["java_class"],
-    0x4Cu8 => &["lha"],
     0x7Fu8 => &["elf"],
 };
 
+/// File types by lowercase MIME type, including common aliases.
+pub static FILE_TYPES_BY_MIME_TYPE: phf::Map<&'static str, &'static [FileType]> = phf_map! {
+    "image/jpeg" => &[FileType::Jpeg],
+    "image/jpg" => &[FileType::Jpeg],
+    "image/pjpeg" => &[FileType::Jpeg],
+    "image/png" => &[FileType::Png],
+    "image/gif" => &[FileType::Gif],
+    "image/bmp" => &[FileType::Bmp],
+    "image/x-ms-bmp" => &[FileType::Bmp],
+    "image/tiff" => &[FileType::Tiff, FileType::BigTiff],
+    "application/pdf" => &[FileType::Pdf],
+    "audio/mpeg" => &[FileType::Mp3],
+    "audio/mp3" => &[FileType::Mp3],
+    "application/ogg" => &[FileType::Ogg],
+    "audio/ogg" => &[FileType::Ogg, FileType::Opus],
+    "video/ogg" => &[FileType::Ogg],
+    "audio/flac" => &[FileType::Flac],
+    "audio/x-flac" => &[FileType::Flac],
+    "image/webp" => &[FileType::WebP],
+    "audio/wav" => &[FileType::Wav],
+    "audio/wave" => &[FileType::Wav],
+    "audio/x-wav" => &[FileType::Wav],
+    "video/x-msvideo" => &[FileType::Avi],
+    "video/avi" => &[FileType::Avi],
+    "video/mpeg" => &[FileType::Mpeg],
+    "video/mp2t" => &[FileType::MpegTs],
+    "video/x-matroska" => &[FileType::Mkv],
+    "video/x-flv" => &[FileType::Flv],
+    "video/mp4" => &[FileType::Mp4],
+    "application/zip" => &[FileType::Zip],
+    "application/x-zip-compressed" => &[FileType::Zip],
+    "application/gzip" => &[FileType::Gzip],
+    "application/x-gzip" => &[FileType::Gzip],
+    "application/x-bzip2" => &[FileType::Bzip2],
+    "application/java-vm" => &[FileType::JavaClass],
+    "application/x-lzh-compressed" => &[FileType::Lha],
+    "application/x-executable" => &[FileType::Elf],
+    "application/x-elf" => &[FileType::Elf],
+    "application/x-tar" => &[FileType::Tar],
+    "application/x-iso9660-image" => &[FileType::Iso9660],
+    "image/avif" => &[FileType::Avif],
+    "image/heic" => &[FileType::Heic],
+    "image/heif" => &[FileType::Heic],
+    "image/jxl" => &[FileType::JpegXl],
+    "image/x-icon" => &[FileType::Ico],
+    "image/vnd.microsoft.icon" => &[FileType::Ico],
+    "image/x-win-bitmap" => &[FileType::Cur],
+    "image/vnd.adobe.photoshop" => &[FileType::Psd],
+    "video/quicktime" => &[FileType::Mov],
+    "video/webm" => &[FileType::WebM],
+    "audio/webm" => &[FileType::WebM],
+    "audio/opus" => &[FileType::Opus],
+    "audio/aac" => &[FileType::Aac],
+    "audio/x-aac" => &[FileType::Aac],
+    "audio/midi" => &[FileType::Midi],
+    "audio/x-midi" => &[FileType::Midi],
+    "application/vnd.rar" => &[FileType::Rar],
+    "application/x-rar-compressed" => &[FileType::Rar],
+    "application/x-7z-compressed" => &[FileType::SevenZip],
+    "application/vnd.ms-cab-compressed" => &[FileType::Cab],
+    "application/x-archive" => &[FileType::Ar],
+    "application/vnd.debian.binary-package" => &[FileType::Deb],
+    "application/x-debian-package" => &[FileType::Deb],
+    "application/x-rpm" => &[FileType::Rpm],
+    "application/x-cpio" => &[FileType::Cpio],
+    "application/x-xz" => &[FileType::Xz],
+    "application/zstd" => &[FileType::Zstd],
+    "application/x-lz4" => &[FileType::Lz4],
+    "application/x-lzma" => &[FileType::Lzma],
+    "application/x-compress" => &[FileType::UnixCompress],
+    "application/vnd.microsoft.portable-executable" => &[FileType::Pe],
+    "application/x-msdownload" => &[FileType::Pe],
+    "application/x-dosexec" => &[FileType::Pe],
+    "application/x-mach-binary" => &[FileType::MachO],
+    "application/wasm" => &[FileType::Wasm],
+    "application/vnd.android.dex" => &[FileType::Dex],
+    "application/x-python-code" => &[FileType::Pyc],
+    "application/x-shellscript" => &[FileType::Script],
+    "text/x-shellscript" => &[FileType::Script],
+    "application/x-msi" => &[FileType::Msi],
+    "font/woff" => &[FileType::Woff],
+    "application/font-woff" => &[FileType::Woff],
+    "font/woff2" => &[FileType::Woff2],
+    "font/ttf" => &[FileType::Ttf],
+    "application/x-font-ttf" => &[FileType::Ttf],
+    "font/otf" => &[FileType::Otf],
+    "application/x-font-opentype" => &[FileType::Otf],
+    "font/collection" => &[FileType::Ttc],
+    "application/vnd.ms-fontobject" => &[FileType::Eot],
+    "application/vnd.sqlite3" => &[FileType::Sqlite],
+    "application/x-sqlite3" => &[FileType::Sqlite],
+    "application/vnd.apache.parquet" => &[FileType::Parquet],
+    "application/vnd.apache.arrow.file" => &[FileType::Arrow],
+    "application/rtf" => &[FileType::Rtf],
+    "text/rtf" => &[FileType::Rtf],
+    "application/postscript" => &[FileType::PostScript, FileType::Eps],
+    "image/x-eps" => &[FileType::Eps],
+    "image/vnd.djvu" => &[FileType::DjVu],
+    "image/fits" => &[FileType::Fits],
+    "application/fits" => &[FileType::Fits],
+};
+
+/// File types by lowercase file extension without the dot.
+pub static FILE_TYPES_BY_EXTENSION: phf::Map<&'static str, &'static [FileType]> = phf_map! {
+    "jpg" => &[FileType::Jpeg],
+    "jpeg" => &[FileType::Jpeg],
+    "jpe" => &[FileType::Jpeg],
+    "jfif" => &[FileType::Jpeg],
+    "png" => &[FileType::Png],
+    "gif" => &[FileType::Gif],
+    "bmp" => &[FileType::Bmp],
+    "dib" => &[FileType::Bmp],
+    "tif" => &[FileType::Tiff, FileType::BigTiff],
+    "tiff" => &[FileType::Tiff, FileType::BigTiff],
+    "pdf" => &[FileType::Pdf],
+    "mp3" => &[FileType::Mp3],
+    "ogg" => &[FileType::Ogg],
+    "oga" => &[FileType::Ogg],
+    "ogv" => &[FileType::Ogg],
+    "ogx" => &[FileType::Ogg],
+    "flac" => &[FileType::Flac],
+    "webp" => &[FileType::WebP],
+    "wav" => &[FileType::Wav],
+    "avi" => &[FileType::Avi],
+    "ts" => &[FileType::MpegTs],
+    "m2ts" => &[FileType::MpegTs],
+    "mts" => &[FileType::MpegTs],
+    "mpg" => &[FileType::Mpeg],
+    "mpeg" => &[FileType::Mpeg],
+    "m1v" => &[FileType::Mpeg],
+    "mkv" => &[FileType::Mkv],
+    "flv" => &[FileType::Flv],
+    "mp4" => &[FileType::Mp4],
+    "m4v" => &[FileType::Mp4],
+    "zip" => &[FileType::Zip],
+    "gz" => &[FileType::Gzip],
+    "tgz" => &[FileType::Gzip],
+    "bz2" => &[FileType::Bzip2],
+    "tbz2" => &[FileType::Bzip2],
+    "class" => &[FileType::JavaClass],
+    "lzh" => &[FileType::Lha],
+    "lha" => &[FileType::Lha],
+    "elf" => &[FileType::Elf],
+    "so" => &[FileType::Elf],
+    "tar" => &[FileType::Tar],
+    "iso" => &[FileType::Iso9660],
+    "avif" => &[FileType::Avif],
+    "heic" => &[FileType::Heic],
+    "heif" => &[FileType::Heic],
+    "jxl" => &[FileType::JpegXl],
+    "ico" => &[FileType::Ico],
+    "cur" => &[FileType::Cur],
+    "psd" => &[FileType::Psd],
+    "psb" => &[FileType::Psd],
+    "mov" => &[FileType::Mov],
+    "qt" => &[FileType::Mov],
+    "webm" => &[FileType::WebM],
+    "opus" => &[FileType::Opus],
+    "aac" => &[FileType::Aac],
+    "mid" => &[FileType::Midi],
+    "midi" => &[FileType::Midi],
+    "rar" => &[FileType::Rar],
+    "7z" => &[FileType::SevenZip],
+    "cab" => &[FileType::Cab],
+    "a" => &[FileType::Ar],
+    "ar" => &[FileType::Ar],
+    "deb" => &[FileType::Deb],
+    "rpm" => &[FileType::Rpm],
+    "cpio" => &[FileType::Cpio],
+    "xz" => &[FileType::Xz],
+    "txz" => &[FileType::Xz],
+    "zst" => &[FileType::Zstd],
+    "lz4" => &[FileType::Lz4],
+    "lzma" => &[FileType::Lzma],
+    "z" => &[FileType::UnixCompress],
+    "exe" => &[FileType::Pe],
+    "dll" => &[FileType::Pe],
+    "sys" => &[FileType::Pe],
+    "dylib" => &[FileType::MachO],
+    "wasm" => &[FileType::Wasm],
+    "dex" => &[FileType::Dex],
+    "pyc" => &[FileType::Pyc],
+    "pyo" => &[FileType::Pyc],
+    "sh" => &[FileType::Script],
+    "msi" => &[FileType::Msi],
+    "woff" => &[FileType::Woff],
+    "woff2" => &[FileType::Woff2],
+    "ttf" => &[FileType::Ttf],
+    "otf" => &[FileType::Otf],
+    "ttc" => &[FileType::Ttc],
+    "otc" => &[FileType::Ttc],
+    "eot" => &[FileType::Eot],
+    "sqlite" => &[FileType::Sqlite],
+    "sqlite3" => &[FileType::Sqlite],
+    "db" => &[FileType::Sqlite],
+    "parquet" => &[FileType::Parquet],
+    "arrow" => &[FileType::Arrow],
+    "feather" => &[FileType::Arrow],
+    "rtf" => &[FileType::Rtf],
+    "ps" => &[FileType::PostScript],
+    "eps" => &[FileType::Eps],
+    "epsf" => &[FileType::Eps],
+    "djvu" => &[FileType::DjVu],
+    "djv" => &[FileType::DjVu],
+    "fits" => &[FileType::Fits],
+    "fit" => &[FileType::Fits],
+    "fts" => &[FileType::Fits],
+};
+
 /// Encoding to detect for locales
 pub static ENCODINGS_BY_LOCALE: phf::Map<&'static str, &'static encoding_rs::Encoding> = phf::phf_map! {
     "af-za" => encoding_rs::WINDOWS_1252, // Afrikaans (South Africa)
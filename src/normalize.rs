@@ -0,0 +1,46 @@
+use unicode_normalization::{is_nfc_quick, is_nfkc_quick, IsNormalized, UnicodeNormalization};
+
+/// The Unicode normalization form applied to decoded text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Normalization {
+    /// Canonical composition, joining decomposed accents like those in windows-1258 text.
+    Nfc,
+    /// Compatibility composition, also folding compatibility characters like full-width forms.
+    Nfkc,
+}
+
+/// Get the content with proper encoding, normalized to the form.
+pub fn auto_encode_bytes_normalized(html: &[u8], form: Normalization) -> String {
+    crate::AutoEncoder::builder()
+        .normalization(form)
+        .build()
+        .decode(html)
+        .unwrap_or_default()
+}
+
+/// Get the content with proper encoding, normalized to the form. Pass in a proper encoding label like SHIFT_JIS.
+pub fn encode_bytes_normalized(html: &[u8], label: &str, form: Normalization) -> String {
+    crate::AutoEncoder::builder()
+        .normalization(form)
+        .build()
+        .decode_with_label(html, label)
+        .unwrap_or_default()
+}
+
+/// Normalize the text of `output` after `start` in place, skipping text that is already normalized.
+pub(crate) fn normalize_tail(output: &mut String, start: usize, form: Normalization) {
+    let tail = &output[start..];
+    let quick = match form {
+        Normalization::Nfc => is_nfc_quick(tail.chars()),
+        Normalization::Nfkc => is_nfkc_quick(tail.chars()),
+    };
+    if quick == IsNormalized::Yes {
+        return;
+    }
+    let normalized: String = match form {
+        Normalization::Nfc => tail.nfc().collect(),
+        Normalization::Nfkc => tail.nfkc().collect(),
+    };
+    output.truncate(start);
+    output.push_str(&normalized);
+}
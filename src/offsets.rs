@@ -0,0 +1,122 @@
+use crate::split::Split;
+use encoding_rs::Encoding;
+
+/// A compact map between byte offsets in the original input and byte offsets in the decoded UTF-8 text.
+///
+/// Offsets are sampled at points where the decoder holds no pending bytes, roughly every
+/// `interval` characters. Conversions between samples re-decode the short span in between,
+/// so they need the original input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OffsetMap {
+    encoding: &'static Encoding,
+    samples: Vec<(usize, usize)>,
+}
+
+impl OffsetMap {
+    /// The encoding the input was decoded with, after byte order mark sniffing.
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    /// The sampled `(original, decoded)` offset pairs in increasing order.
+    pub fn samples(&self) -> &[(usize, usize)] {
+        &self.samples
+    }
+
+    /// Convert an offset in the original input to an offset in the decoded text.
+    /// Offsets inside a character map to the start of the character.
+    pub fn to_decoded(&self, original: &[u8], offset: usize) -> usize {
+        let offset = offset.min(original.len());
+        let index = self.samples.partition_point(|&(o, _)| o <= offset);
+        let (sample_original, sample_decoded) = self.samples[index.saturating_sub(1)];
+        if offset <= sample_original {
+            return sample_decoded;
+        }
+        let mut output = String::new();
+        crate::decode_chunk(
+            &mut self.encoding.new_decoder_without_bom_handling(),
+            &original[sample_original..offset],
+            false,
+            &mut output,
+        );
+        sample_decoded + output.len()
+    }
+
+    /// Convert an offset in the decoded text to an offset in the original input.
+    /// Offsets inside a character map to the start of its bytes in the input.
+    pub fn to_original(&self, original: &[u8], offset: usize) -> usize {
+        let index = self.samples.partition_point(|&(_, d)| d <= offset);
+        let (sample_original, sample_decoded) = self.samples[index.saturating_sub(1)];
+        if offset <= sample_decoded {
+            return sample_original;
+        }
+
+        let mut decoder = self.encoding.new_decoder_without_bom_handling();
+        let mut output = String::with_capacity(16);
+        let mut decoded = sample_decoded;
+        let mut char_start = sample_original;
+
+        for pos in sample_original..original.len() {
+            output.clear();
+            crate::decode_chunk(&mut decoder, &original[pos..pos + 1], false, &mut output);
+            if output.is_empty() {
+                continue;
+            }
+            // a replacement for earlier bytes comes out together with the character ending here
+            let last = output.char_indices().last().map_or(0, |(index, _)| index);
+            for (index, ch) in output.char_indices() {
+                let start = if index > 0 && index == last { pos } else { char_start };
+                if decoded + ch.len_utf8() > offset {
+                    return start;
+                }
+                decoded += ch.len_utf8();
+            }
+            char_start = pos + 1;
+            if decoded == offset {
+                return char_start;
+            }
+        }
+
+        original.len()
+    }
+}
+
+/// Decode the content with the encoding, also returning an [`OffsetMap`] sampled roughly every `interval` characters.
+///
+/// The text is identical to decoding without a map. Stateful encodings like ISO-2022-JP
+/// are only sampled at both ends, so each conversion re-decodes from the start of the input.
+pub fn decode_with_offsets(
+    html: &[u8],
+    encoding: &'static Encoding,
+    interval: usize,
+) -> (String, OffsetMap) {
+    let (encoding, start) = match Encoding::for_bom(html) {
+        Some((encoding, bom_len)) => (encoding, bom_len),
+        None => (encoding, 0),
+    };
+    let interval = interval.max(1);
+    let split = Split::for_encoding(encoding);
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut output = String::new();
+    let mut samples = vec![(start, 0)];
+    let mut pos = start;
+    let mut chars = 0usize;
+
+    if split != Split::None {
+        while let Some(boundary) = split.next_boundary(html, pos + interval) {
+            let written = output.len();
+            crate::decode_chunk(&mut decoder, &html[pos..boundary], false, &mut output);
+            chars += output[written..].chars().count();
+            pos = boundary;
+            if chars >= interval {
+                samples.push((pos, output.len()));
+                chars = 0;
+            }
+        }
+    }
+
+    crate::decode_chunk(&mut decoder, &html[pos..], true, &mut output);
+    samples.push((html.len(), output.len()));
+
+    (output, OffsetMap { encoding, samples })
+}
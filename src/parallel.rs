@@ -0,0 +1,76 @@
+use crate::split::Split;
+use encoding_rs::Encoding;
+use rayon::prelude::*;
+
+/// The smallest piece of input worth decoding on its own thread.
+const MIN_SEGMENT_LEN: usize = 256 * 1024;
+
+/// Get the content with proper encoding, decoding large documents in parallel. Output is identical to [`crate::encode_bytes`].
+pub fn par_encode_bytes(html: &[u8], label: &str) -> String {
+    match Encoding::for_label(label.as_bytes()) {
+        Some(encoding) => par_decode(html, encoding, segment_len(html)),
+        None => String::new(),
+    }
+}
+
+/// Get the content with proper encoding, decoding large documents in parallel. Output is identical to [`crate::auto_encode_bytes`].
+pub fn par_auto_encode_bytes(html: &[u8]) -> String {
+    match crate::AutoEncoder::default().detect(html) {
+        Ok(encoding) if !html.is_empty() => par_decode(html, encoding, segment_len(html)),
+        _ => String::new(),
+    }
+}
+
+/// Spread the content over the thread pool without going below the minimum segment length.
+fn segment_len(html: &[u8]) -> usize {
+    (html.len() / rayon::current_num_threads()).max(MIN_SEGMENT_LEN)
+}
+
+/// Split the content at safe boundaries about `segment_len` bytes apart and decode the segments in parallel.
+pub(crate) fn par_decode(html: &[u8], encoding: &'static Encoding, segment_len: usize) -> String {
+    let (encoding, start) = match Encoding::for_bom(html) {
+        Some((encoding, bom_len)) => (encoding, bom_len),
+        None => (encoding, 0),
+    };
+    let split = Split::for_encoding(encoding);
+    let input = &html[start..];
+
+    if split == Split::None || input.len() < segment_len * 2 {
+        let mut output = String::new();
+        crate::decode_chunk(
+            &mut encoding.new_decoder_without_bom_handling(),
+            input,
+            true,
+            &mut output,
+        );
+        return output;
+    }
+
+    let mut bounds = vec![0];
+    let mut from = segment_len;
+    while let Some(boundary) = split.next_boundary(input, from) {
+        bounds.push(boundary);
+        from = boundary + segment_len;
+    }
+    bounds.push(input.len());
+
+    let segments: Vec<String> = bounds
+        .par_windows(2)
+        .map(|range| {
+            let mut output = String::new();
+            crate::decode_chunk(
+                &mut encoding.new_decoder_without_bom_handling(),
+                &input[range[0]..range[1]],
+                true,
+                &mut output,
+            );
+            output
+        })
+        .collect();
+
+    let mut output = String::with_capacity(segments.iter().map(String::len).sum());
+    for segment in &segments {
+        output.push_str(segment);
+    }
+    output
+}
@@ -0,0 +1,44 @@
+use crate::detect::charset_declarations;
+use encoding_rs::Encoding;
+
+/// Rewrite the charset declared by decoded HTML to UTF-8 and strip a leading byte order mark.
+///
+/// The `<meta charset>`, `http-equiv` content charset and XML declaration labels found in
+/// the first 1024 bytes are replaced with `utf-8`.
+///
+/// ```rust
+/// let mut html = String::from(r#"<meta charset="shift_jis"><p>hi</p>"#);
+/// auto_encoder::rewrite_declared_charset(&mut html);
+/// assert_eq!(html, r#"<meta charset="utf-8"><p>hi</p>"#);
+/// ```
+pub fn rewrite_declared_charset(html: &mut String) {
+    rewrite_tail(html, 0, 1024);
+}
+
+/// Rewrite the declared charset of the text of `output` after `start` in place,
+/// looking at the first `window` bytes of that text.
+pub(crate) fn rewrite_tail(output: &mut String, start: usize, window: usize) {
+    let mut window = window;
+    if output[start..].starts_with('\u{FEFF}') {
+        output.replace_range(start..start + '\u{FEFF}'.len_utf8(), "");
+        window = window.saturating_sub('\u{FEFF}'.len_utf8());
+    }
+    let end = output.len().min(start + window);
+    let spans = charset_declarations(&output.as_bytes()[start..end]);
+    for span in spans.into_iter().rev() {
+        let range = start + span.start..start + span.end;
+        if !output[range.clone()].eq_ignore_ascii_case("utf-8") {
+            output.replace_range(range, "utf-8");
+        }
+    }
+}
+
+/// The length of the decoded text of the first `window` bytes of `html`, the part of the
+/// output the prescan of the input looked at.
+pub(crate) fn decoded_window_len(html: &[u8], encoding: &'static Encoding, window: usize) -> usize {
+    let window = &html[..html.len().min(window)];
+    let mut decoder = encoding.new_decoder();
+    let mut decoded = String::new();
+    crate::decode_chunk(&mut decoder, window, window.len() == html.len(), &mut decoded);
+    decoded.len()
+}
@@ -0,0 +1,166 @@
+use crate::detect::detect_file_type;
+use crate::file_type::FileType;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Bytes inspected by [`classify_content`], the same window git uses.
+pub const SNIFF_LEN: usize = 8000;
+
+/// Printable bytes tolerated per binary data byte before content counts as binary, as in git.
+const PRINTABLE_PER_CONTROL: usize = 128;
+
+/// Bytes per binary data byte other than null UTF-16 without a byte order mark can have.
+/// The low bytes of CJK characters hit the binary data range about one time in twenty.
+const UTF16_BYTES_PER_CONTROL: usize = 8;
+
+/// What content looks like from its bytes alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ContentClass {
+    /// A format recognized by its signature.
+    Known(FileType),
+    /// Text, with the Unicode encoding when a byte order mark or the bytes tell it.
+    Text(Option<&'static Encoding>),
+    /// Unknown content with null bytes or too many other binary data bytes.
+    Binary,
+}
+
+impl ContentClass {
+    /// Whether the content should be decoded as text, including detected scripts.
+    pub fn is_text(self) -> bool {
+        match self {
+            ContentClass::Known(file_type) => !file_type.is_binary(),
+            ContentClass::Text(_) => true,
+            ContentClass::Binary => false,
+        }
+    }
+}
+
+/// Classify content as a known format, text or unknown binary data from its first [`SNIFF_LEN`] bytes.
+///
+/// Unknown content is binary when it has a null byte or more than one mimesniff binary data
+/// byte per 128 printable bytes, unless it reads as UTF-16. UTF-16 with or without a byte
+/// order mark is text.
+///
+/// ```rust
+/// use auto_encoder::{classify_content, ContentClass};
+///
+/// assert_eq!(classify_content(b"plain text"), ContentClass::Text(Some(auto_encoder::encoding_rs::UTF_8)));
+/// assert_eq!(classify_content(b"\x08\x96\x01\x12\x07testing"), ContentClass::Binary);
+/// ```
+pub fn classify_content(content: &[u8]) -> ContentClass {
+    let sample = &content[..content.len().min(SNIFF_LEN)];
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return ContentClass::Text(Some(encoding));
+    }
+    if let Some(file_type) = detect_file_type(content) {
+        return ContentClass::Known(file_type);
+    }
+
+    let (mut nulls, mut controls, mut high) = (0, 0, 0);
+    for &byte in sample {
+        nulls += usize::from(byte == 0);
+        controls += usize::from(byte != 0 && is_binary_data_byte(byte));
+        high += usize::from(byte >= 0x80);
+    }
+    if nulls > 0 || controls * PRINTABLE_PER_CONTROL > sample.len() - controls {
+        // complete content of odd length cannot be UTF-16
+        let complete = sample.len() == content.len();
+        // without null bytes UTF-16 is mostly outside ASCII, unlike binary data around ASCII strings
+        let ascii = nulls == 0 && high * UTF16_BYTES_PER_CONTROL < sample.len();
+        if (complete && sample.len() % 2 == 1)
+            || controls * UTF16_BYTES_PER_CONTROL > sample.len()
+            || ascii
+        {
+            return ContentClass::Binary;
+        }
+        return match utf16_without_bom(sample) {
+            Some(encoding) => ContentClass::Text(Some(encoding)),
+            None => ContentClass::Binary,
+        };
+    }
+
+    match std::str::from_utf8(sample) {
+        Ok(_) => ContentClass::Text(Some(UTF_8)),
+        // a sequence cut by the end of the sample is still UTF-8
+        Err(error) if error.error_len().is_none() => ContentClass::Text(Some(UTF_8)),
+        Err(_) => ContentClass::Text(None),
+    }
+}
+
+/// Check if content is probably text, for formats [`is_binary_file`](crate::is_binary_file) does not know.
+///
+/// ```rust
+/// assert!(auto_encoder::is_probably_text("ĥello".as_bytes()));
+/// assert!(!auto_encoder::is_probably_text(&[0x0A, 0x04, 0x00, 0x00, 0x80, 0x3F]));
+/// ```
+pub fn is_probably_text(content: &[u8]) -> bool {
+    classify_content(content).is_text()
+}
+
+/// Check for a byte from the binary data byte range of the mimesniff standard.
+fn is_binary_data_byte(byte: u8) -> bool {
+    matches!(byte, 0x00..=0x08 | 0x0B | 0x0E..=0x1A | 0x1C..=0x1F)
+}
+
+/// Detect UTF-16 without a byte order mark, picking the valid byte order with the most code
+/// units in common scripts when at least three quarters of them are.
+fn utf16_without_bom(sample: &[u8]) -> Option<&'static Encoding> {
+    let units = sample.len() / 2;
+    [(UTF_16BE, true), (UTF_16LE, false)]
+        .into_iter()
+        .filter_map(|(encoding, big_endian)| Some((utf16_score(sample, big_endian)?, encoding)))
+        .filter(|&(score, _)| score > 0 && score * 4 >= units * 3)
+        // little endian wins ties, being far more common
+        .max_by_key(|&(score, _)| score)
+        .map(|(_, encoding)| encoding)
+}
+
+/// Count the code units in common scripts, `None` when the sample is not UTF-16 text
+/// because of null code units, unpaired surrogates or too much binary data.
+fn utf16_score(sample: &[u8], big_endian: bool) -> Option<usize> {
+    let mut units = sample.chunks_exact(2).map(|pair| {
+        if big_endian {
+            u16::from_be_bytes([pair[0], pair[1]])
+        } else {
+            u16::from_le_bytes([pair[0], pair[1]])
+        }
+    });
+    let (mut total, mut controls, mut common) = (0, 0, 0);
+    while let Some(unit) = units.next() {
+        total += 1;
+        match unit {
+            0 => return None,
+            0xD800..=0xDBFF => match units.next() {
+                Some(0xDC00..=0xDFFF) => total += 1,
+                // the pair may be cut by the end of the sample
+                None => break,
+                Some(_) => return None,
+            },
+            0xDC00..=0xDFFF => return None,
+            0x01..=0x1F => controls += usize::from(is_binary_data_byte(unit as u8)),
+            _ => {}
+        }
+        // code units ending in a null byte are mostly ASCII read in the wrong byte order
+        common += usize::from(is_common_unit(unit) && (unit <= 0xFF || unit & 0xFF != 0));
+    }
+    (controls * PRINTABLE_PER_CONTROL <= total - controls).then_some(common)
+}
+
+/// Check for a code unit of ASCII text, a common alphabet, CJK or Hangul.
+fn is_common_unit(unit: u16) -> bool {
+    matches!(
+        unit,
+        0x09 | 0x0A
+            | 0x0D
+            | 0x20..=0x7E
+            | 0xA0..=0x024F
+            | 0x0370..=0x052F
+            | 0x0590..=0x06FF
+            | 0x0900..=0x0E7F
+            | 0x2000..=0x206F
+            | 0x3000..=0x30FF
+            | 0x4E00..=0x9FFF
+            | 0xAC00..=0xD7A3
+            | 0xFF00..=0xFFEF
+    )
+}
@@ -0,0 +1,96 @@
+use encoding_rs::Encoding;
+
+/// How input in an encoding can be cut into pieces that decode to the same text independently.
+///
+/// At a boundary the decoder has no pending bytes, so the text decoded so far is final.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Split {
+    /// Every byte is a character, so any offset is safe.
+    Any,
+    /// Any offset at the start of a UTF-8 sequence following a complete character is safe.
+    CharStart,
+    /// Offsets after a run of ASCII bytes are safe for stateless multibyte encodings.
+    AsciiRun,
+    /// Even offsets not inside a surrogate pair are safe for UTF-16, the flag telling big endian.
+    Utf16(bool),
+    /// Stateful encodings like ISO-2022-JP can not be split.
+    None,
+}
+
+/// The ASCII run length that guarantees no multibyte sequence is pending.
+/// The longest sequence is four bytes in GB18030.
+const ASCII_RUN: usize = 4;
+
+impl Split {
+    /// The split strategy for the encoding, after the byte order mark has been handled.
+    pub(crate) fn for_encoding(encoding: &'static Encoding) -> Split {
+        if encoding.is_single_byte() {
+            Split::Any
+        } else if encoding == encoding_rs::UTF_8 {
+            Split::CharStart
+        } else if encoding == encoding_rs::SHIFT_JIS
+            || encoding == encoding_rs::EUC_JP
+            || encoding == encoding_rs::EUC_KR
+            || encoding == encoding_rs::BIG5
+            || encoding == encoding_rs::GBK
+            || encoding == encoding_rs::GB18030
+        {
+            Split::AsciiRun
+        } else if encoding == encoding_rs::UTF_16BE || encoding == encoding_rs::UTF_16LE {
+            Split::Utf16(encoding == encoding_rs::UTF_16BE)
+        } else {
+            Split::None
+        }
+    }
+
+    /// Find the first safe offset at or after `from`, if any before the end of the input.
+    pub(crate) fn next_boundary(self, input: &[u8], from: usize) -> Option<usize> {
+        if from >= input.len() {
+            return None;
+        }
+        match self {
+            Split::Any => Some(from),
+            Split::CharStart => (from..input.len())
+                .find(|&pos| input[pos] & 0xC0 != 0x80 && ends_with_char(&input[..pos])),
+            Split::AsciiRun => {
+                let mut run = input[from.saturating_sub(ASCII_RUN)..from]
+                    .iter()
+                    .rev()
+                    .take_while(|b| b.is_ascii())
+                    .count();
+                for (pos, &b) in input[from..].iter().enumerate() {
+                    if run >= ASCII_RUN {
+                        return Some(from + pos);
+                    }
+                    run = if b.is_ascii() { run + 1 } else { 0 };
+                }
+                None
+            }
+            Split::Utf16(big_endian) => (from + from % 2..input.len())
+                .step_by(2)
+                .find(|&pos| pos == 0 || !ends_with_high_surrogate(&input[..pos], big_endian)),
+            Split::None => None,
+        }
+    }
+}
+
+/// Whether the UTF-8 input is empty or ends with a complete and valid character.
+fn ends_with_char(input: &[u8]) -> bool {
+    if input.is_empty() {
+        return true;
+    }
+    match input.iter().rev().take(4).position(|&b| b & 0xC0 != 0x80) {
+        Some(pos) => std::str::from_utf8(&input[input.len() - pos - 1..]).is_ok(),
+        None => false,
+    }
+}
+
+/// Whether the last UTF-16 code unit of the input is a high surrogate waiting for its pair.
+fn ends_with_high_surrogate(input: &[u8], big_endian: bool) -> bool {
+    let high = if big_endian {
+        input[input.len() - 2]
+    } else {
+        input[input.len() - 1]
+    };
+    (0xD8..=0xDB).contains(&high)
+}
@@ -0,0 +1,340 @@
+use crate::auto::AutoEncoder;
+use crate::error::Error;
+use crate::limit::{LimitCheck, LimitPolicy};
+use encoding_rs::Encoding;
+
+/// The state of the incremental decoder.
+enum State {
+    /// Buffering input until the encoding can be resolved.
+    Sniffing(Vec<u8>),
+    /// Decoding input as it arrives.
+    Decoding(encoding_rs::Decoder),
+    /// Decoding stopped at an error or the output limit, the rest of the input is dropped.
+    Stopped,
+}
+
+/// Incrementally decode chunks of a document into UTF-8.
+///
+/// The encoding is resolved with [`AutoEncoder::detect`] so both produce the same output.
+/// Input is buffered until the prescan window has been seen for the declared charset.
+/// Documents without a declared charset, byte order mark or locale are buffered to the end for content detection.
+///
+/// Strictness, the output limit and charset rewriting apply as they do for [`AutoEncoder::decode`],
+/// Unicode normalization does not. Text pushed before an error is kept.
+pub struct StreamDecoder {
+    config: AutoEncoder,
+    state: State,
+    encoding: Option<&'static Encoding>,
+    /// Input bytes decoded so far.
+    read: usize,
+    /// Output emitted so far in the unit of the output limit.
+    emitted: usize,
+    error: Option<Error>,
+    truncated: bool,
+}
+
+impl Default for StreamDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamDecoder {
+    /// Create a new decoder for a single document. Same as [`crate::auto_encode_bytes`].
+    pub fn new() -> Self {
+        Self::with_config(AutoEncoder::default())
+    }
+
+    /// Create a new decoder for a single document using the configuration.
+    pub fn with_config(config: AutoEncoder) -> Self {
+        Self {
+            config,
+            state: State::Sniffing(Vec::new()),
+            encoding: None,
+            read: 0,
+            emitted: 0,
+            error: None,
+            truncated: false,
+        }
+    }
+
+    /// The encoding used for decoding, if resolved yet.
+    pub fn encoding(&self) -> Option<&'static Encoding> {
+        self.encoding
+    }
+
+    /// The error decoding stopped at, if any.
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+
+    /// Whether the output was cut short at the output limit.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Push a chunk of input, appending any decoded text to `output`.
+    pub fn push(&mut self, chunk: &[u8], output: &mut String) {
+        let window = self.config.prescan_window;
+        match &mut self.state {
+            State::Sniffing(buffer) => {
+                let buffered = buffer.len();
+                buffer.extend_from_slice(chunk);
+                // resolve once when the buffer first fills the window
+                if buffer.len() < window || (buffered >= window && buffered > 0) {
+                    return;
+                }
+                if let Some(encoding) = self.config.detect_declared(buffer) {
+                    let buffer = std::mem::take(buffer);
+                    self.start(encoding, &buffer, false, output);
+                }
+            }
+            State::Decoding(_) => self.decode(chunk, false, output),
+            State::Stopped => (),
+        }
+    }
+
+    /// Signal the end of the input, appending the remaining decoded text to `output`.
+    pub fn finish(&mut self, output: &mut String) {
+        match &mut self.state {
+            State::Sniffing(buffer) => {
+                let buffer = std::mem::take(buffer);
+                if buffer.is_empty() {
+                    self.state = State::Stopped;
+                    return;
+                }
+                let encoding = self.config.detect(&buffer);
+                self.start(encoding, &buffer, true, output);
+            }
+            State::Decoding(_) => self.decode(b"", true, output),
+            State::Stopped => (),
+        }
+    }
+
+    /// Start decoding with the resolved encoding and the buffered input.
+    fn start(
+        &mut self,
+        encoding: Result<&'static Encoding, Error>,
+        buffer: &[u8],
+        last: bool,
+        output: &mut String,
+    ) {
+        match encoding {
+            Ok(encoding) => {
+                self.encoding = Some(encoding);
+                self.state = State::Decoding(encoding.new_decoder());
+                self.decode(buffer, last, output);
+            }
+            Err(error) => self.stop(Some(error)),
+        }
+    }
+
+    /// Decode a chunk applying the strictness, charset rewriting and output limit.
+    fn decode(&mut self, chunk: &[u8], last: bool, output: &mut String) {
+        let State::Decoding(decoder) = &mut self.state else {
+            return;
+        };
+        let encoding = decoder.encoding();
+        let start = output.len();
+        let result = if self.config.strict {
+            crate::decode_strict_chunk(decoder, chunk, self.read, last, output)
+        } else {
+            crate::decode_chunk(decoder, chunk, last, output);
+            Ok(())
+        };
+        if let Err(error) = result {
+            output.truncate(start);
+            return self.stop(Some(error));
+        }
+        // the first output holds the whole prescan window
+        if self.read == 0 && self.config.rewrite_declared_charset {
+            let window =
+                crate::rewrite::decoded_window_len(chunk, encoding, self.config.prescan_window);
+            crate::rewrite::rewrite_tail(output, start, window);
+        }
+        self.read += chunk.len();
+
+        let Some(limit) = self.config.output_limit else {
+            return;
+        };
+        let remaining = limit.with_value(limit.value().saturating_sub(self.emitted));
+        let Some(cut) = LimitCheck::new(remaining, start).exceeded(output) else {
+            self.emitted += limit.measure(&output[start..]);
+            return;
+        };
+        let cut = match self.config.limit_policy {
+            LimitPolicy::Error => {
+                output.truncate(start);
+                return self.stop(Some(Error::OutputLimit {
+                    limit: limit.value(),
+                }));
+            }
+            LimitPolicy::Truncate => cut,
+            #[cfg(feature = "graphemes")]
+            LimitPolicy::TruncateGraphemes => crate::limit::grapheme_floor(output, start, cut),
+        };
+        output.truncate(cut);
+        self.truncated = true;
+        self.stop(None);
+    }
+
+    /// Stop decoding, dropping the rest of the input.
+    fn stop(&mut self, error: Option<Error>) {
+        self.state = State::Stopped;
+        self.error = error;
+    }
+}
+
+/// Decode a `Stream` of byte chunks into a `Stream` of UTF-8 `String` chunks.
+#[cfg(feature = "futures")]
+pub struct DecodeStream<S> {
+    inner: S,
+    decoder: StreamDecoder,
+    finished: bool,
+}
+
+#[cfg(feature = "futures")]
+impl<S> DecodeStream<S> {
+    /// Wrap a stream of byte chunks.
+    pub fn new(inner: S) -> Self {
+        Self::with_config(inner, AutoEncoder::default())
+    }
+
+    /// Wrap a stream of byte chunks, decoding with the configuration.
+    pub fn with_config(inner: S, config: AutoEncoder) -> Self {
+        Self {
+            inner,
+            decoder: StreamDecoder::with_config(config),
+            finished: false,
+        }
+    }
+
+    /// The encoding used for decoding, if resolved yet.
+    pub fn encoding(&self) -> Option<&'static Encoding> {
+        self.decoder.encoding()
+    }
+
+    /// The error decoding stopped at, if any. The stream ends early on error.
+    pub fn error(&self) -> Option<&Error> {
+        self.decoder.error()
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<S, B> futures_core::Stream for DecodeStream<S>
+where
+    S: futures_core::Stream<Item = B> + Unpin,
+    B: AsRef<[u8]>,
+{
+    type Item = String;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<String>> {
+        use std::task::Poll;
+
+        let this = self.get_mut();
+        let mut output = String::new();
+
+        while !this.finished {
+            match std::pin::Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Ready(Some(chunk)) => this.decoder.push(chunk.as_ref(), &mut output),
+                Poll::Ready(None) => {
+                    this.finished = true;
+                    this.decoder.finish(&mut output);
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+            if !output.is_empty() {
+                return Poll::Ready(Some(output));
+            }
+        }
+
+        Poll::Ready(None)
+    }
+}
+
+/// Decode a tokio `AsyncRead` of any encoding into an `AsyncRead` of UTF-8.
+#[cfg(feature = "tokio")]
+pub struct DecodeReader<R> {
+    inner: R,
+    decoder: StreamDecoder,
+    input: Box<[u8]>,
+    output: String,
+    position: usize,
+    finished: bool,
+}
+
+#[cfg(feature = "tokio")]
+impl<R> DecodeReader<R> {
+    /// Wrap a reader of encoded bytes.
+    pub fn new(inner: R) -> Self {
+        Self::with_config(inner, AutoEncoder::default())
+    }
+
+    /// Wrap a reader of encoded bytes, decoding with the configuration.
+    pub fn with_config(inner: R, config: AutoEncoder) -> Self {
+        Self {
+            inner,
+            decoder: StreamDecoder::with_config(config),
+            input: vec![0u8; 8192].into_boxed_slice(),
+            output: String::new(),
+            position: 0,
+            finished: false,
+        }
+    }
+
+    /// The encoding used for decoding, if resolved yet.
+    pub fn encoding(&self) -> Option<&'static Encoding> {
+        self.decoder.encoding()
+    }
+
+    /// The error decoding stopped at, if any. Reading ends early on error.
+    pub fn error(&self) -> Option<&Error> {
+        self.decoder.error()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R> tokio::io::AsyncRead for DecodeReader<R>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        use std::task::Poll;
+
+        let this = self.get_mut();
+
+        loop {
+            if this.position < this.output.len() {
+                let pending = &this.output.as_bytes()[this.position..];
+                let len = pending.len().min(buf.remaining());
+                buf.put_slice(&pending[..len]);
+                this.position += len;
+                return Poll::Ready(Ok(()));
+            }
+            if this.finished {
+                return Poll::Ready(Ok(()));
+            }
+
+            this.output.clear();
+            this.position = 0;
+
+            let mut input = tokio::io::ReadBuf::new(&mut this.input);
+            match std::pin::Pin::new(&mut this.inner).poll_read(cx, &mut input) {
+                Poll::Ready(Ok(())) if input.filled().is_empty() => {
+                    this.finished = true;
+                    this.decoder.finish(&mut this.output);
+                }
+                Poll::Ready(Ok(())) => this.decoder.push(input.filled(), &mut this.output),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::encode::{encode_chunk, UnmappablePolicy};
+use crate::error::Error;
+use encoding_rs::{CoderResult, Encoding};
+
+/// The size of the intermediate UTF-8 buffer used between the decoder and encoder.
+const SCRATCH_LEN: usize = 8192;
+
+/// Convert bytes from one encoding to another, like SHIFT_JIS to EUC-JP.
+///
+/// Malformed input is decoded to U+FFFD before being encoded with the policy.
+pub fn transcode(
+    input: &[u8],
+    from: &str,
+    to: &str,
+    policy: UnmappablePolicy,
+) -> Result<Vec<u8>, Error> {
+    let from = Encoding::for_label(from.as_bytes())
+        .ok_or_else(|| Error::UnknownLabel(from.to_string()))?;
+    let to =
+        Encoding::for_label(to.as_bytes()).ok_or_else(|| Error::UnknownLabel(to.to_string()))?;
+    let mut output = Vec::with_capacity(input.len());
+    Transcoder::new(from, to, policy).push(input, true, &mut output)?;
+    Ok(output)
+}
+
+/// Incrementally convert a byte stream from one encoding to another.
+///
+/// Text passes through a fixed-size UTF-8 buffer, so memory use does not grow with the input.
+pub struct Transcoder {
+    decoder: encoding_rs::Decoder,
+    encoder: encoding_rs::Encoder,
+    policy: UnmappablePolicy,
+    scratch: String,
+    decoded: usize,
+}
+
+impl Transcoder {
+    /// Create a transcoder between the encodings.
+    pub fn new(from: &'static Encoding, to: &'static Encoding, policy: UnmappablePolicy) -> Self {
+        Self {
+            decoder: from.new_decoder(),
+            encoder: to.new_encoder(),
+            policy,
+            scratch: String::with_capacity(SCRATCH_LEN),
+            decoded: 0,
+        }
+    }
+
+    /// The encoding input is decoded from. This changes if the input starts with a byte order mark.
+    pub fn from_encoding(&self) -> &'static Encoding {
+        self.decoder.encoding()
+    }
+
+    /// The encoding output is written in.
+    pub fn to_encoding(&self) -> &'static Encoding {
+        self.encoder.encoding()
+    }
+
+    /// Push a chunk of input, appending the converted bytes to `output`. Set `last` on the final chunk.
+    pub fn push(
+        &mut self,
+        mut input: &[u8],
+        last: bool,
+        output: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        loop {
+            self.scratch.clear();
+            let (result, read, _) = self
+                .decoder
+                .decode_to_string(input, &mut self.scratch, last);
+            input = &input[read..];
+            let done = result == CoderResult::InputEmpty;
+            encode_chunk(
+                &mut self.encoder,
+                &self.scratch,
+                self.decoded,
+                last && done,
+                self.policy,
+                output,
+            )?;
+            self.decoded += self.scratch.len();
+            if done {
+                return Ok(());
+            }
+        }
+    }
+}